@@ -0,0 +1,136 @@
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use num::Complex;
+
+use newtonian_rustsody::fractals::{Background, Fractal, Kernel, Rectangle, Sampling, SaveOptions};
+use newtonian_rustsody::random::Random;
+
+fn view() -> Rectangle<f64> {
+    Rectangle { left: -5f64, top: -5f64, right: 5f64, bottom: 5f64 }
+}
+
+fn fractal(size: usize, kernel: Kernel) -> Fractal {
+    let mut fractal = Fractal::with_coefficients(
+        (size, size),
+        view(),
+        vec![
+            Complex::new(-0.2796455185190574, -8.619337302126723),
+            Complex::new(7.591418031049244, 4.167755685364256),
+            Complex::new(-9.121138413779903, -6.79613957297315),
+            Complex::new(9.197246762941262, 8.190568781916397),
+            Complex::new(5.366325985514713, -1.1587722090698378),
+        ]
+    );
+    fractal.set_kernel(kernel);
+    fractal
+}
+
+/// Evenly spread over the view, `side` to a side.
+fn starts(side: usize) -> Vec<Complex<f64>> {
+    let view = view();
+    let step = |low: f64, high: f64, i: usize| low + (high - low)*((i as f64) + 0.5f64)/(side as f64);
+
+    (0..side*side)
+        .map(|i| Complex::new(step(view.left, view.right, i % side), step(view.top, view.bottom, i / side)))
+        .collect()
+}
+
+fn root_finding(c: &mut Criterion) {
+    let mut group = c.benchmark_group("root_finding");
+
+    let starts = starts(64);
+    group.throughput(Throughput::Elements(starts.len() as u64));
+
+    // always the same polynomials, so runs on different branches are timing the same work
+    for degree in [3, 5, 8, 12] {
+        let newton = Fractal::with_seed((1, 1), view(), &Random { degree, ..Random::default() }, 1).newton::<f64>();
+        group.bench_with_input(BenchmarkId::from_parameter(degree), &starts, |b, starts| {
+            b.iter(|| starts.iter().filter_map(|s| newton.get_root(*s)).count())
+        });
+    }
+
+    group.finish();
+}
+
+fn kernels(c: &mut Criterion) {
+    let mut group = c.benchmark_group("kernel");
+    group.sample_size(20);
+
+    for (name, kernel) in [("scalar", Kernel::Scalar), ("simd", Kernel::Simd)] {
+        let mut f = fractal(256, kernel);
+        group.bench_function(format!("{}/f64", name), |b| b.iter(|| f.generate_as::<f64>()));
+
+        let mut f = fractal(256, kernel);
+        group.bench_function(format!("{}/f32", name), |b| b.iter(|| f.generate_as::<f32>()));
+    }
+
+    group.finish();
+}
+
+fn sampling(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sampling");
+    group.sample_size(10);
+
+    // the bigger the image, the more of it is inside of basins rather than on their borders
+    for size in [256, 512, 1024] {
+        group.throughput(Throughput::Elements((size*size) as u64));
+
+        for (name, sampling) in [("brute_force", Sampling::BruteForce), ("subdivision", Sampling::Subdivision)] {
+            let mut f = fractal(size, Kernel::Simd);
+            f.set_sampling(sampling);
+            group.bench_function(format!("{}/{}", name, size), |b| b.iter(|| f.generate()));
+        }
+    }
+
+    group.finish();
+}
+
+fn coloring(c: &mut Criterion) {
+    let mut group = c.benchmark_group("coloring");
+
+    let mut f = fractal(800, Kernel::Simd);
+    f.generate();
+    group.throughput(Throughput::Elements(800*800));
+    group.bench_function("800", |b| b.iter(|| f.cycle_palette()));
+
+    group.finish();
+}
+
+fn saving(c: &mut Criterion) {
+    let mut group = c.benchmark_group("saving");
+    group.sample_size(20);
+
+    let path = std::env::temp_dir().join(format!("newtonian_rustsody_{}_bench.png", std::process::id()));
+    let path = path.to_str().expect("utf-8 temp dir");
+
+    let mut f = fractal(800, Kernel::Simd);
+    f.generate();
+    for (name, options) in [
+        ("opaque", SaveOptions { background: Background::Opaque, grid: false }),
+        ("grid", SaveOptions { background: Background::Opaque, grid: true }),
+    ] {
+        group.bench_function(name, |b| b.iter(|| f.save_to_path(path, options).expect("saved image")));
+    }
+
+    group.finish();
+    let _ = std::fs::remove_file(path);
+}
+
+/// Longer and pickier than criterion's defaults, so a change between two runs is more likely
+/// to be the code than the machine.
+fn config() -> Criterion {
+    Criterion::default()
+        .warm_up_time(Duration::from_secs(3))
+        .measurement_time(Duration::from_secs(10))
+        .noise_threshold(0.03)
+        .significance_level(0.01)
+}
+
+criterion_group! {
+    name = benches;
+    config = config();
+    targets = root_finding, kernels, sampling, coloring, saving
+}
+criterion_main!(benches);
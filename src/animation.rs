@@ -0,0 +1,257 @@
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::sync::mpsc::Receiver;
+
+use image::{Delay, Frame, RgbaImage};
+use image::codecs::gif::{GifEncoder, Repeat};
+use num::complex::Complex;
+use serde::Deserialize;
+
+use crate::export::{self, ExportEvent};
+use crate::fractals::{Fractal, Rectangle, SaveOptions};
+use crate::grid::Grid;
+
+/// How the zoom speeds up and slows down over the animation.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    pub fn parse(name: &str) -> Option<Easing> {
+        match name {
+            "linear" => Some(Easing::Linear),
+            "ease-in" => Some(Easing::EaseIn),
+            "ease-out" => Some(Easing::EaseOut),
+            "ease-in-out" => Some(Easing::EaseInOut),
+            _ => None,
+        }
+    }
+
+    /// How far along the zoom is `t` of the way through the animation, both from 0 to 1.
+    pub fn apply(self, t: f64) -> f64 {
+        match self {
+            Easing::Linear => t,
+            Easing::EaseIn => t*t,
+            Easing::EaseOut => t*(2f64 - t),
+            Easing::EaseInOut => t*t*(3f64 - 2f64*t),
+        }
+    }
+}
+
+/// How zoom videos get made, whatever they zoom between.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Video {
+    pub frames: usize,
+    pub easing: Easing,
+    /// An animated GIF of the frames as well as the PNGs
+    pub gif: bool,
+    pub fps: u32,
+}
+
+/// A zoom from one view to another over `frames` frames.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Zoom {
+    pub from: Rectangle<f64>,
+    pub to: Rectangle<f64>,
+    pub frames: usize,
+    pub easing: Easing,
+}
+
+impl Zoom {
+    /// Zooming in on `point` by `factor`, ending up with it in the middle.
+    pub fn towards(from: Rectangle<f64>, point: Complex<f64>, factor: f64, frames: usize, easing: Easing) -> Zoom {
+        let (half_width, half_height) = ((from.right - from.left)/(2f64*factor), (from.bottom - from.top)/(2f64*factor));
+
+        Zoom {
+            from,
+            to: Rectangle {
+                left: point.re - half_width,
+                top: point.im - half_height,
+                right: point.re + half_width,
+                bottom: point.im + half_height,
+            },
+            frames,
+            easing,
+        }
+    }
+
+    /// The view for frame `frame`, counting from 0. The scale changes geometrically, always by
+    /// the same factor from one frame to the next before easing, about the one point that's in
+    /// the same place in both views, so the zoom looks like it's going at a constant speed.
+    pub fn view(&self, frame: usize) -> Rectangle<f64> {
+        let t = if self.frames > 1 { (frame as f64)/((self.frames - 1) as f64) } else { 1f64 };
+        let t = self.easing.apply(t);
+
+        let along = |from_low: f64, from_high: f64, to_low: f64, to_high: f64| {
+            let ratio = (to_high - to_low)/(from_high - from_low);
+            if (ratio - 1f64).abs() < 1e-9f64 {
+                // no zoom, just a pan
+                return (from_low + (to_low - from_low)*t, from_high + (to_high - from_high)*t);
+            }
+
+            let fixed = (to_low - ratio*from_low)/(1f64 - ratio);
+            let scale = ratio.powf(t);
+            (fixed + (from_low - fixed)*scale, fixed + (from_high - fixed)*scale)
+        };
+
+        let (left, right) = along(self.from.left, self.from.right, self.to.left, self.to.right);
+        let (top, bottom) = along(self.from.top, self.from.bottom, self.to.top, self.to.bottom);
+
+        Rectangle { left, top, right, bottom }
+    }
+}
+
+/// Writes frames into numbered PNGs in a directory, and into `animation.gif` there too if the video
+/// has one.
+pub struct Frames {
+    directory: String,
+    gif: Option<(GifEncoder<BufWriter<File>>, u32)>,
+    written: usize,
+}
+
+impl Frames {
+    pub fn new(directory: &str, video: &Video) -> Result<Frames, String> {
+        fs::create_dir_all(directory).map_err(|e| e.to_string())?;
+
+        let gif = if video.gif {
+            let file = File::create(format!("{}/animation.gif", directory)).map_err(|e| e.to_string())?;
+            let mut encoder = GifEncoder::new(BufWriter::new(file));
+            encoder.set_repeat(Repeat::Infinite).map_err(|e| e.to_string())?;
+            Some((encoder, video.fps))
+        } else {
+            None
+        };
+
+        Ok(Frames { directory: String::from(directory), gif, written: 0 })
+    }
+
+    /// Adds what `fractal` last generated as the next frame.
+    pub fn write(&mut self, fractal: &Fractal, options: SaveOptions) -> Result<(), String> {
+        let size = fractal.size();
+
+        let mut pixels = fractal.pixels_with(options.background);
+        if options.grid {
+            Grid::new(fractal.view(), size).bake(&mut pixels, size);
+        }
+
+        let image = RgbaImage::from_raw(size.0 as u32, size.1 as u32, pixels).expect("whole frame");
+        image.save(format!("{}/frame_{:05}.png", self.directory, self.written)).map_err(|e| e.to_string())?;
+
+        if let Some((gif, fps)) = &mut self.gif {
+            let delay = Delay::from_numer_denom_ms(1000, *fps);
+            gif.encode_frame(Frame::from_parts(image, 0, 0, delay)).map_err(|e| e.to_string())?;
+        }
+
+        self.written += 1;
+        Ok(())
+    }
+}
+
+/// Renders every frame of `zoom` at `fractal`'s size into `directory`, see `Frames`, with
+/// `progress` like `export::export`'s.
+pub fn render(
+    fractal: &Fractal,
+    zoom: &Zoom,
+    video: &Video,
+    directory: &str,
+    options: SaveOptions,
+    mut progress: impl FnMut(f64)
+) -> Result<(), String> {
+    let mut frames = Frames::new(directory, video)?;
+
+    // reused for every frame, like the bands of an export
+    let mut frame = fractal.clone();
+
+    for i in 0..zoom.frames {
+        frame.set_view(zoom.view(i));
+        frame.generate();
+        frames.write(&frame, options)?;
+
+        progress(((i + 1) as f64)/(zoom.frames as f64));
+    }
+
+    Ok(())
+}
+
+/// Starts rendering `zoom` on its own thread, like `export::spawn`.
+pub fn spawn(fractal: Fractal, zoom: Zoom, video: Video, directory: String, options: SaveOptions) -> Receiver<ExportEvent> {
+    export::in_background(directory.clone(), move |progress| render(&fractal, &zoom, &video, &directory, options, progress))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::fractals::Background;
+    use crate::testing::{temp_path, unity};
+
+    fn view(size: f64) -> Rectangle<f64> {
+        Rectangle { left: -size, top: -size, right: size, bottom: size }
+    }
+
+    fn assert_close(a: Rectangle<f64>, b: Rectangle<f64>) {
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-12f64*a.abs().max(b.abs()).max(1f64);
+        assert!(close(a.left, b.left) && close(a.top, b.top) && close(a.right, b.right) && close(a.bottom, b.bottom), "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn zooms_go_at_a_constant_speed() {
+        let zoom = Zoom::towards(view(2f64), Complex::new(0.5f64, -0.25f64), 1e6f64, 31, Easing::Linear);
+
+        assert_close(zoom.view(0), zoom.from);
+        assert_close(zoom.view(30), zoom.to);
+
+        let width = |i: usize| zoom.view(i).right - zoom.view(i).left;
+        for i in 1..30 {
+            let (before, after) = (width(i)/width(i-1), width(i+1)/width(i));
+            assert!((before - after).abs() < 1e-9f64, "{} then {}", before, after);
+        }
+
+        // and the point being zoomed into only ever gets closer to the middle
+        let off_center = |i: usize| {
+            let view = zoom.view(i);
+            ((view.left + view.right)/2f64 - 0.5f64).abs()/(view.right - view.left)
+        };
+        for i in 1..31 {
+            assert!(off_center(i) <= off_center(i-1));
+        }
+    }
+
+    #[test]
+    fn easing_starts_and_ends_in_place() {
+        for easing in [Easing::Linear, Easing::EaseIn, Easing::EaseOut, Easing::EaseInOut] {
+            assert_eq!(easing.apply(0f64), 0f64);
+            assert_eq!(easing.apply(1f64), 1f64);
+            assert!(easing.apply(0.25f64) < easing.apply(0.75f64));
+        }
+
+        // the same size both ends is just a pan
+        let pan = Zoom { from: view(1f64), to: Rectangle { left: 0f64, top: 0f64, right: 2f64, bottom: 2f64 }, frames: 3, easing: Easing::Linear };
+        assert_close(pan.view(1), Rectangle { left: -0.5f64, top: -0.5f64, right: 1.5f64, bottom: 1.5f64 });
+    }
+
+    #[test]
+    fn every_frame_gets_written() {
+        let directory = temp_path("zoom");
+        let directory = directory.as_str();
+
+        let fractal = Fractal::with_coefficients((24, 16), view(2f64), unity(3));
+        let zoom = Zoom::towards(view(2f64), Complex::new(1f64, 0f64), 10f64, 3, Easing::EaseInOut);
+        let video = Video { frames: 3, easing: Easing::EaseInOut, gif: true, fps: 30 };
+
+        render(&fractal, &zoom, &video, directory, SaveOptions { background: Background::Opaque, grid: false }, |_| {}).expect("rendered");
+
+        let mut files: Vec<String> = fs::read_dir(directory).expect("frames")
+            .map(|entry| entry.expect("frame").file_name().into_string().expect("utf-8 name"))
+            .collect();
+        files.sort();
+        fs::remove_dir_all(directory).expect("removed frames");
+
+        assert_eq!(files, ["animation.gif", "frame_00000.png", "frame_00001.png", "frame_00002.png"]);
+    }
+}
@@ -0,0 +1,162 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::fractals::{Fractal, Rectangle, SaveOptions};
+use crate::grid::Grid;
+
+/// How many rows get rendered at a time, which is what keeps the memory down on big posters.
+const BAND: usize = 256;
+
+/// What an export running in the background reports back.
+pub enum ExportEvent {
+    /// How much of it is done, from 0 to 1.
+    Progress(f64),
+    Finished { path: String, elapsed: Duration },
+    Failed(String),
+}
+
+/// `view` widened in one direction so it's got the same aspect ratio as `size`, about the same center.
+pub fn fit(view: &Rectangle<f64>, size: (usize, usize)) -> Rectangle<f64> {
+    let (width, height) = (view.right - view.left, view.bottom - view.top);
+    let aspect = (size.0 as f64)/(size.1 as f64);
+
+    let (new_width, new_height) = if width.abs()/height.abs() < aspect {
+        (width.signum()*height.abs()*aspect, height)
+    } else {
+        (width, height.signum()*width.abs()/aspect)
+    };
+
+    let (center_x, center_y) = ((view.left + view.right)/2f64, (view.top + view.bottom)/2f64);
+    Rectangle {
+        left: center_x - new_width/2f64,
+        top: center_y - new_height/2f64,
+        right: center_x + new_width/2f64,
+        bottom: center_y + new_height/2f64,
+    }
+}
+
+/// Renders `fractal`'s view at `size` into a PNG at `path`, a band of rows at a time straight
+/// into the encoder so the whole image is never in memory. `progress` is told how far along
+/// it is after every band.
+pub fn export(fractal: &Fractal, path: &str, size: (usize, usize), options: SaveOptions, mut progress: impl FnMut(f64)) -> Result<(), String> {
+    let view = fit(fractal.view(), size);
+    let grid = if options.grid { Some(Grid::new(&view, size)) } else { None };
+
+    // the same one all the way down, so roots found in one band keep their colors in the next
+    let mut band = fractal.clone();
+    band.set_view(view);
+
+    let file = File::create(path).map_err(|e| e.to_string())?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), size.0 as u32, size.1 as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    // with the view it actually covers, not the window's
+    for (keyword, text) in band.metadata() {
+        encoder.add_text_chunk(String::from(keyword), text).map_err(|e| e.to_string())?;
+    }
+
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    let mut stream = writer.stream_writer().map_err(|e| e.to_string())?;
+
+    let row_height = (view.bottom - view.top)/(size.1 as f64);
+
+    for top in (0..size.1).step_by(BAND) {
+        let rows = BAND.min(size.1 - top);
+
+        band.set_size((size.0, rows));
+        band.set_view(Rectangle {
+            left: view.left,
+            top: view.top + (top as f64)*row_height,
+            right: view.right,
+            bottom: view.top + ((top + rows) as f64)*row_height,
+        });
+        band.generate();
+
+        let mut pixels = band.pixels_with(options.background);
+        if let Some(grid) = &grid {
+            grid.bake_rows(&mut pixels, size.0, top);
+        }
+
+        stream.write_all(&pixels).map_err(|e| e.to_string())?;
+        progress(((top + rows) as f64)/(size.1 as f64));
+    }
+
+    stream.finish().map_err(|e| e.to_string())
+}
+
+/// Starts exporting a copy of `fractal` on its own thread, leaving the one on screen alone.
+pub fn spawn(fractal: Fractal, path: String, size: (usize, usize), options: SaveOptions) -> Receiver<ExportEvent> {
+    in_background(path.clone(), move |progress| export(&fractal, &path, size, options, progress))
+}
+
+/// Runs `work` on its own thread, passing on how far along it is and how it went, about `path`.
+pub fn in_background(path: String, work: impl FnOnce(&mut dyn FnMut(f64)) -> Result<(), String> + Send + 'static) -> Receiver<ExportEvent> {
+    let (events, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        let beginning = Instant::now();
+        let finished = work(&mut |done| {
+            let _ = events.send(ExportEvent::Progress(done));
+        });
+
+        let _ = events.send(match finished {
+            Ok(()) => ExportEvent::Finished { path, elapsed: beginning.elapsed() },
+            Err(e) => ExportEvent::Failed(format!("{}: {}", path, e)),
+        });
+    });
+
+    receiver
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::fractals::Background;
+    use crate::testing::{temp_path, unity};
+
+    #[test]
+    fn views_are_widened_to_fit() {
+        let view = Rectangle { left: -1f64, top: -1f64, right: 1f64, bottom: 1f64 };
+
+        assert_eq!(fit(&view, (200, 100)), Rectangle { left: -2f64, top: -1f64, right: 2f64, bottom: 1f64 });
+        assert_eq!(fit(&view, (100, 400)), Rectangle { left: -1f64, top: -4f64, right: 1f64, bottom: 4f64 });
+    }
+
+    #[test]
+    fn bands_add_up_to_the_whole_image() {
+        let size = (48, 2*BAND + 40);
+        let view = Rectangle { left: -1f64, top: -5f64, right: 1f64, bottom: 5f64 };
+        let cubic = unity(3);
+
+        let path = temp_path("export.png");
+        let path = path.as_str();
+
+        let fractal = Fractal::with_coefficients((10, 10), view, cubic.clone());
+        let mut reported = Vec::new();
+        export(&fractal, path, size, SaveOptions { background: Background::Opaque, grid: false }, |done| reported.push(done))
+            .expect("exported");
+
+        let exported = image::open(path).expect("exported png").to_rgba8();
+        let reader = png::Decoder::new(File::open(path).expect("exported png")).read_info().expect("png header");
+        let recorded_view = reader.info().uncompressed_latin1_text.iter()
+            .find(|chunk| chunk.keyword == "View")
+            .map(|chunk| chunk.text.clone());
+        std::fs::remove_file(path).expect("removed export");
+
+        let mut whole = Fractal::with_coefficients(size, fit(&view, size), cubic);
+        whole.generate();
+
+        assert_eq!(reported.len(), 3);
+        assert_eq!(reported.last(), Some(&1f64));
+        assert_eq!(exported.dimensions(), (size.0 as u32, size.1 as u32));
+        assert_eq!(recorded_view, Some(format!("{:?}", fit(&view, size))));
+
+        // the bands' edges are worked out a little differently, so a pixel right on a boundary could flip
+        let different = exported.as_raw().chunks_exact(4).zip(whole.pixels().chunks_exact(4)).filter(|(a, b)| a != b).count();
+        assert!(different*1000 < size.0*size.1, "{} pixels differ", different);
+    }
+}
@@ -1,13 +1,28 @@
-use rand::Rng;
+use rand::{Rng, SeedableRng};
+use rand_pcg::Pcg64Mcg;
 use num::complex::Complex;
-use num::pow;
+use num::Float;
 
-use std::time::Instant;
+use std::fs::File;
+use std::io::BufWriter;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 
-use ggez::{Context, GameResult};
-use ggez::graphics::{self, Drawable, DrawParam, Image, Rect, BlendMode};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug)]
+use crate::grid::Grid;
+use crate::polynomial;
+use crate::random::Random;
+use crate::simd::SimdFloat;
+
+/// Handed out to every new set of pixels, so they can be told apart even across clones.
+static GENERATIONS: AtomicU64 = AtomicU64::new(0);
+
+fn next_generation() -> u64 {
+    GENERATIONS.fetch_add(1, Ordering::Relaxed)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct Rectangle<T> {
     pub left: T,
     pub top: T,
@@ -15,60 +30,233 @@ pub struct Rectangle<T> {
     pub bottom: T,
 }
 
+/// A name, and the colors given to the roots, in order.
+type Palette = (&'static str, &'static [(u8, u8, u8)]);
+
+const PALETTES: [Palette; 7] = [
+    ("yellow dusty", &[(0xcf, 0x6a, 0x4c), (0xf9, 0xee, 0x98), (0x75, 0x87, 0xa6), (0x9b, 0x85, 0x9d)]),
+    ("yellow dusty (5)", &[(0xcf, 0x6a, 0x4c), (0xf9, 0xee, 0x98), (0x75, 0x87, 0xa6), (0x9b, 0x85, 0x9d), (0x5f, 0x5a, 0x60)]),
+    ("dusty", &[(0xcf, 0x6a, 0x4c), (0x8f, 0x9d, 0x6a), (0x75, 0x87, 0xa6), (0x9b, 0x85, 0x9d)]),
+    ("forgot", &[(0xfe, 0xc4, 0x18), (0x06, 0xb6, 0xef), (0x81, 0x5b, 0xa4), (0x5b, 0xc4, 0xbf)]),
+    ("red sinister", &[(0xd7, 0x37, 0x37), (0x51, 0x6a, 0xec), (0xb8, 0x54, 0xd4), (0x7b, 0x59, 0xc0)]),
+    ("grape popsicle", &[(0xe5, 0x8b, 0xf2), (0x6a, 0xdb, 0xde), (0x9d, 0x83, 0xf0)]),
+    ("candymelon", &[(0xff, 0xb3, 0x3c), (0xfa, 0xe6, 0x70), (0xcc, 0xeb, 0x61), (0xff, 0x9a, 0x81), (0x8d, 0xe9, 0x87)]),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sampling {
+    /// Every pixel
+    BruteForce,
+    /// Only the borders of regions, filling in the ones that are all the same
+    Subdivision,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kernel {
+    /// One pixel at a time
+    Scalar,
+    /// Several pixels at a time, see `simd.rs`
+    Simd,
+}
+
+/// Where the extra samples go inside a supersampled pixel.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Pattern {
+    /// Evenly spaced
+    Grid,
+    /// Somewhere random in each cell of the grid
+    Jittered,
+    /// The grid turned so no two samples share a row or a column, which is a lot better on
+    /// nearly straight edges for the same number of samples
+    RotatedGrid,
+}
+
+/// Taking `size`x`size` samples per pixel of a full resolution generation and averaging their colors.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Supersampling {
+    pub pattern: Pattern,
+    pub size: usize,
+    /// Only the pixels with a neighbour going to a different root, where the edges are
+    pub adaptive: bool,
+}
+
+impl Supersampling {
+    /// Where the samples go in `pixel`, as offsets from its top left corner. Jittered ones are
+    /// different in every pixel, but always the same in the same one.
+    fn offsets(&self, (x, y): (usize, usize)) -> Vec<(f64, f64)> {
+        let n = self.size as f64;
+        let mut rng = Pcg64Mcg::seed_from_u64(((y as u64) << 32) | x as u64);
+
+        (0..self.size)
+            .flat_map(|j| (0..self.size).map(move |i| (i as f64, j as f64)))
+            .map(|(i, j)| match self.pattern {
+                Pattern::Grid => ((i + 0.5f64)/n, (j + 0.5f64)/n),
+                Pattern::Jittered => ((i + rng.gen::<f64>())/n, (j + rng.gen::<f64>())/n),
+                Pattern::RotatedGrid => ((i + (n - j - 0.5f64)/n)/n, (j + (i + 0.5f64)/n)/n),
+            })
+            .collect()
+    }
+}
+
+impl std::fmt::Display for Supersampling {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let pattern = match self.pattern {
+            Pattern::Grid => "grid",
+            Pattern::Jittered => "jittered",
+            Pattern::RotatedGrid => "rotated grid",
+        };
+
+        write!(f, "{}{}x{} {}", if self.adaptive { "adaptive " } else { "" }, self.size, self.size, pattern)
+    }
+}
+
+/// The pixels that got supersampled, by index, with what each of their samples found.
+type Supersamples = Vec<(usize, Vec<Option<(usize, u32)>>)>;
+
+/// What the pixels that don't go to any root look like in a saved image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Background {
+    /// Black, like on screen
+    Opaque,
+    /// See-through, for putting the fractal over something else
+    Transparent,
+}
+
+/// What goes into a saved image besides the fractal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SaveOptions {
+    pub background: Background,
+    /// The axes and grid, see `grid.rs`
+    pub grid: bool,
+}
+
+/// How long finding the roots and coloring them in took.
+#[derive(Debug, Clone, Copy)]
+pub struct Timings {
+    pub roots: Duration,
+    pub texture: Duration,
+}
+
+/// Where an orbit ended up.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    /// Settled after `iters` like `get_root` counts them, on `roots[root]` if it's been found already
+    Converged { root: Option<usize>, iters: u32 },
+    /// Kept going round the same `period` points
+    Cycle { period: usize },
+    /// Neither, by the time it ran out of iterations
+    Unsettled,
+}
+
+/// Every iterate of Newton's method from a starting point, the start included.
+#[derive(Debug, Clone)]
+pub struct Orbit {
+    pub points: Vec<Complex<f64>>,
+    pub outcome: Outcome,
+}
+
+#[derive(Clone)]
 pub struct Fractal {
     size: (usize, usize),
     view: Rectangle<f64>,
+    sampling: Sampling,
+    kernel: Kernel,
 
     coefficients: Vec<Complex<f64>>,
 
     roots: Vec<Complex<f64>>,
 
+    candidates: Vec<Option<(usize, u32)>>,
+    supersampling: Option<Supersampling>,
+    supersamples: Supersamples,
+    /// What the coefficients came from, if they're random and haven't been changed since
+    seed: Option<(u64, Random)>,
+    palette: usize,
     pixels: Vec<u8>,
+    generation: u64,
 }
 
 impl Fractal {
-    const DIFF_STEP: f64 = 1e-8;
     const TOLERANCE: f64 = 1e-4;
-    const MAX_ITERS: u32 = 100;
-
-    #[allow(dead_code)]
-    pub fn with_random_coefficients(_ctx: &mut Context, size: (usize, usize), view: Rectangle<f64>, degree: u32) -> Fractal {
-        let mut coefficients = Vec::with_capacity((degree+1) as usize);
-
-        let mut rng = rand::thread_rng();
+    pub const MAX_ITERS: u32 = 100;
 
-        for _ in 0..degree+1 {
-            coefficients.push(Complex::new(rng.gen_range(-10f64, 10f64), rng.gen_range(-10f64, 10f64)));
-        }
+    /// A random polynomial of degree `degree`, with the coefficients anywhere in [-10, 10] + [-10, 10]i.
+    pub fn with_random_coefficients(size: (usize, usize), view: Rectangle<f64>, degree: u32) -> Fractal {
+        Self::with_seed(size, view, &Random { degree, ..Random::default() }, rand::thread_rng().gen())
+    }
 
-        println!("{:#?}", coefficients);
+    /// The random polynomial `seed` always gives, made the way `random` says.
+    pub fn with_seed(size: (usize, usize), view: Rectangle<f64>, random: &Random, seed: u64) -> Fractal {
+        let mut fractal = Self::with_coefficients(size, view, random.coefficients(seed, &view).0);
+        fractal.seed = Some((seed, *random));
+        fractal
+    }
 
+    pub fn with_coefficients(size: (usize, usize), view: Rectangle<f64>, coefficients: Vec<Complex<f64>>) -> Fractal {
         Fractal {
+            roots: polynomial::roots(&coefficients),
             size,
             view,
+            sampling: Sampling::BruteForce,
+            kernel: Kernel::Simd,
             coefficients,
-            roots: Vec::new(),
+            candidates: Vec::new(),
+            supersampling: None,
+            supersamples: Vec::new(),
+            seed: None,
+            palette: 0,
             pixels: vec![0u8; size.0*size.1*4],
+            generation: next_generation(),
         }
     }
 
-    #[allow(dead_code)]
-    pub fn with_coefficients(_ctx: &mut Context, size: (usize, usize), view: Rectangle<f64>, coefficients: Vec<Complex<f64>>) -> Fractal {
-        Fractal {
-            size,
-            view,
-            coefficients,
-            roots: Vec::new(),
-            pixels: vec![0u8; size.0*size.1*4],
+    pub fn size(&self) -> (usize, usize) {
+        self.size
+    }
+
+    /// Changes the size the next generation comes out in, blanking what's been generated so far.
+    pub fn set_size(&mut self, size: (usize, usize)) {
+        if size == self.size {
+            return;
         }
+
+        self.size = size;
+        self.candidates.clear();
+        self.supersamples.clear();
+        self.pixels = vec![0u8; size.0*size.1*4];
+        self.generation = next_generation();
+    }
+
+    pub fn view(&self) -> &Rectangle<f64> {
+        &self.view
     }
 
-    #[allow(dead_code)]
     pub fn set_view(&mut self, view: Rectangle<f64>) {
         self.view = view;
     }
 
-    #[allow(dead_code)]
+    pub fn sampling(&self) -> Sampling {
+        self.sampling
+    }
+
+    pub fn set_sampling(&mut self, sampling: Sampling) {
+        self.sampling = sampling;
+    }
+
+    pub fn supersampling(&self) -> Option<Supersampling> {
+        self.supersampling
+    }
+
+    /// Sets how full resolution generations get supersampled, if at all. Ones at a lower
+    /// resolution never are, they're only there to be replaced soon.
+    pub fn set_supersampling(&mut self, supersampling: Option<Supersampling>) {
+        self.supersampling = supersampling;
+    }
+
+    pub fn set_kernel(&mut self, kernel: Kernel) {
+        self.kernel = kernel;
+    }
+
     pub fn scale_view(&mut self, scale: Rectangle<f64>) {
         let width = self.view.right - self.view.left;
         let height = self.view.bottom - self.view.top;
@@ -81,92 +269,195 @@ impl Fractal {
         };
     }
 
-    pub fn save_to_file(&self, filename: &str) {
-        image::save_buffer(
-            format!("out/{}", filename),
-            &self.pixels,
-            800, 800,
-            image::ColorType::Rgba8
-        ).expect("saved image");
+    pub fn coefficients(&self) -> &[Complex<f64>] {
+        &self.coefficients
     }
 
-    fn source(&self, z: Complex<f64>) -> Complex<f64> {
-        let mut result = Complex::new(0f64, 0f64);
+    /// Switches to a new polynomial. Its roots are worked out up front, in the same order as
+    /// the closest of the old ones, so anything that moved only a little keeps its color.
+    pub fn set_coefficients(&mut self, coefficients: Vec<Complex<f64>>) {
+        self.roots = polynomial::match_order(&self.roots, polynomial::roots(&coefficients));
+        self.coefficients = coefficients;
+        self.seed = None;
+    }
 
-        for (i, c) in self.coefficients.iter().enumerate() {
-            result += c*pow(z, i);
-        }
+    pub fn seed(&self) -> Option<u64> {
+        self.seed.map(|(seed, _)| seed)
+    }
 
-        result
+    /// Switches to the random polynomial `seed` gives, made the way `random` says for the current view,
+    /// and says whether it's everything `random` asked for, like `Random::coefficients`. It's nothing
+    /// like the old one, so its roots get colored just like in `with_seed`.
+    pub fn set_seed(&mut self, random: &Random, seed: u64) -> bool {
+        let (coefficients, fits) = random.coefficients(seed, &self.view);
+
+        self.roots.clear();
+        self.set_coefficients(coefficients);
+        self.seed = Some((seed, *random));
+        fits
     }
 
-    fn source_deriv_reciprocal(&self, z: Complex<f64>) -> Complex<f64> {
-        Self::DIFF_STEP / (self.source(z+Self::DIFF_STEP) - self.source(z))
+    /// Every root, worked out along with the coefficients, plus any others found since.
+    pub fn roots(&self) -> &[Complex<f64>] {
+        &self.roots
     }
 
-    fn get_root(&self, s: Complex<f64>) -> Option<(Complex<f64>, u32)> {
-        let mut z = s;
-        let mut z_prev;
+    /// The pixel `point` is in, the inverse of `point_at`.
+    pub fn pixel_at(&self, point: Complex<f64>) -> (f64, f64) {
+        (
+            (point.re - self.view.left)*(self.size.0 as f64)/(self.view.right - self.view.left),
+            (point.im - self.view.top)*(self.size.1 as f64)/(self.view.bottom - self.view.top)
+        )
+    }
 
-        for i in 0..Self::MAX_ITERS {
-            z_prev = z;
-            z = z - /*Self::a **/ self.source(z) * self.source_deriv_reciprocal(z);
+    /// The point in the view under pixel `(x, y)`.
+    pub fn point_at(&self, (x, y): (f64, f64)) -> Complex<f64> {
+        Complex::new(
+            self.view.left + x*(self.view.right - self.view.left)/(self.size.0 as f64),
+            self.view.top + y*(self.view.bottom - self.view.top)/(self.size.1 as f64)
+        )
+    }
 
-            if (z-z_prev).norm_sqr() < Self::TOLERANCE*Self::TOLERANCE {
-                return Some((z, i));
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    /// Changes whenever the pixels do, and only then.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// The pixels, with the ones that don't converge given `background`.
+    pub fn pixels_with(&self, background: Background) -> Vec<u8> {
+        let mut pixels = self.pixels.clone();
+
+        if background == Background::Transparent {
+            for (pixel, candidate) in pixels.chunks_exact_mut(4).zip(&self.candidates) {
+                if candidate.is_none() {
+                    pixel[3] = 0;
+                }
+            }
+
+            let colors = PALETTES[self.palette].1;
+            for (index, samples) in &self.supersamples {
+                pixels[index*4..][..4].copy_from_slice(&average(colors, samples, background));
             }
         }
 
-        None
+        pixels
+    }
+
+    /// What's saved alongside the pixels, enough to get back to the same fractal.
+    pub fn metadata(&self) -> Vec<(&'static str, String)> {
+        let coefficients: Vec<(f64, f64)> = self.coefficients.iter().map(|c| (c.re, c.im)).collect();
+
+        let mut metadata = vec![
+            ("Software", String::from("Newtonian Rustsody")),
+            ("Coefficients", format!("{:?}", coefficients)),
+            ("View", format!("{:?}", self.view)),
+        ];
+        if let Some((seed, random)) = self.seed {
+            metadata.push(("Seed", seed.to_string()));
+            metadata.push(("Random", random.to_string()));
+        }
+
+        metadata
+    }
+
+    pub fn save_to_file(&self, filename: &str, options: SaveOptions) -> Result<(), String> {
+        self.save_to_path(&format!("out/{}", filename), options)
+    }
+
+    /// Saves a PNG with `metadata` in text chunks.
+    pub fn save_to_path(&self, path: &str, options: SaveOptions) -> Result<(), String> {
+        let mut pixels = self.pixels_with(options.background);
+        if options.grid {
+            Grid::new(&self.view, self.size).bake(&mut pixels, self.size);
+        }
+
+        let file = File::create(path).map_err(|e| e.to_string())?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), self.size.0 as u32, self.size.1 as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        for (keyword, text) in self.metadata() {
+            encoder.add_text_chunk(String::from(keyword), text).map_err(|e| e.to_string())?;
+        }
+
+        encoder.write_header()
+            .and_then(|mut writer| writer.write_image_data(&pixels))
+            .map_err(|e| e.to_string())
     }
 
     pub fn generate(&mut self) {
-        let x_scale = (self.view.right-self.view.left) / (self.size.0 as f64);
-        let y_scale = (self.view.bottom-self.view.top) / (self.size.1 as f64);
+        self.generate_as::<f64>();
+    }
 
-        let mut candidates: Vec<Option<(usize, u32)>> = Vec::with_capacity(self.size.0*self.size.1);
-        //let mut roots: Vec<Complex<f64>> = Vec::new();
+    /// Generates the fractal with the Newton iteration carried out in `T`, e.g. `f32` for a quick preview.
+    pub fn generate_as<T: SimdFloat>(&mut self) {
+        self.generate_scaled::<T>(1, |_| true);
+    }
+
+    /// Generates only every `scale`th pixel in each direction, filling the rest of its
+    /// `scale`x`scale` block with the same color. `keep_going` is told how far along it is,
+    /// from 0 to 1, after every row (supersampling them again being the second half, if it's
+    /// on), and the generation is abandoned (returning `None`, pixels untouched) as soon as it says no.
+    pub fn generate_scaled<T: SimdFloat>(&mut self, scale: usize, mut keep_going: impl FnMut(f64) -> bool) -> Option<Timings> {
+        let newton = Newton::<T>::new(&self.coefficients, Self::TOLERANCE);
+
+        // one sample for every `scale`x`scale` block of pixels
+        let grid_size = (self.size.0.div_ceil(scale), self.size.1.div_ceil(scale));
 
         let beginning = Instant::now();
 
+        let supersampling = self.supersampling.filter(|_| scale == 1);
+        let share = if supersampling.is_some() { 0.5f64 } else { 1f64 };
+        let mut sampling = |done: f64| keep_going(done*share);
+
+        let grid = match self.sampling {
+            Sampling::BruteForce => self.sample_brute_force(&newton, scale, grid_size, &mut sampling)?,
+            Sampling::Subdivision => self.sample_subdivided(&newton, scale, grid_size, &mut sampling)?,
+        };
+
+        let mut candidates: Vec<Option<(usize, u32)>> = Vec::with_capacity(self.size.0*self.size.1);
         for y in 0..self.size.1 {
             for x in 0..self.size.0 {
-                let candidate = self.get_root(Complex::new(
-                    (x as f64)*x_scale + self.view.left,
-                    (y as f64)*y_scale + self.view.top)
-                );
-
-                if let Some((root, iters)) = candidate {
-                    let mut exists = false;
-                    for (index, r) in self.roots.iter().enumerate() {
-                        if (root-r).norm_sqr() < 4f64*Self::TOLERANCE*Self::TOLERANCE {
-                            exists = true;
-                            candidates.push(Some((index, iters))); // this root already found
-                            break;
-                        }
-                    }
-
-                    if !exists {
-                        candidates.push(Some((self.roots.len(), iters))); // this root is new
-                        self.roots.push(root);
-                    }
-                } else {
-                    candidates.push(None); // doesn't converge
-                }
+                candidates.push(grid[(y/scale)*grid_size.0 + x/scale]);
             }
         }
 
-        println!("roots: {}", Instant::now().duration_since(beginning).as_micros());
+        let supersamples = match supersampling {
+            Some(supersampling) => self.supersample(&newton, supersampling, &candidates, |done| keep_going(share + done*(1f64 - share)))?,
+            None => Vec::new(),
+        };
+
+        let roots = beginning.elapsed();
         let beginning = Instant::now();
 
-        //let colors: Vec<(u8, u8, u8)> = vec![(0xfe, 0xc4, 0x18), (0x06, 0xb6, 0xef), (0x81, 0x5b, 0xa4), (0x5b, 0xc4, 0xbf)]; // forgot
-        //let colors: Vec<(u8, u8, u8)> = vec![(0xcf, 0x6a, 0x4c), (0x8f, 0x9d, 0x6a), (0x75, 0x87, 0xa6), (0x9b, 0x85, 0x9d)]; // dusty
-        let colors: Vec<(u8, u8, u8)> = vec![(0xcf, 0x6a, 0x4c), (0xf9, 0xee, 0x98), (0x75, 0x87, 0xa6), (0x9b, 0x85, 0x9d)]; // yellow dusty
-        //let colors: Vec<(u8, u8, u8)> = vec![(0xd7, 0x37, 0x37), (0x51, 0x6a, 0xec), (0xb8, 0x54, 0xd4), (0x7b, 0x59, 0xc0)]; // red sinister
-        //let colors: Vec<(u8, u8, u8)> = vec![(0xe5, 0x8b, 0xf2), (0x6a, 0xdb, 0xde), (0x9d, 0x83, 0xf0), (0x9b, 0x85, 0x9d)]; // grape popsicle (first 3)
-        //let colors: Vec<(u8, u8, u8)> = vec![(0xe5, 0x8b, 0xf2), (0x6a, 0xdb, 0xde), (0x9d, 0x83, 0xf0), (0x9b, 0x85, 0x9d)]; // grape popsicle (first 3)
-        //let colors: Vec<(u8, u8, u8)> = vec![(0xcf, 0x6a, 0x4c), (0xf9, 0xee, 0x98), (0x75, 0x87, 0xa6), (0x9b, 0x85, 0x9d), (0x5f, 0x5a, 0x60)]; // yellow dusty
-        //let colors: Vec<(u8, u8, u8)> = vec![(0xff, 0xb3, 0x3c), (0xfa, 0xe6, 0x70), (0xcc, 0xeb, 0x61), (0xff, 0x9a, 0x81), (0x8d, 0xe9, 0x87)]; // candymelon
+        self.candidates = candidates;
+        self.supersamples = supersamples;
+        self.colorize();
+
+        Some(Timings { roots, texture: beginning.elapsed() })
+    }
+
+    pub fn palette_name(&self) -> &'static str {
+        PALETTES[self.palette].0
+    }
+
+    /// Moves on to the next palette, recoloring what's already been generated.
+    pub fn cycle_palette(&mut self) {
+        self.palette = (self.palette + 1) % PALETTES.len();
+        self.colorize();
+    }
+
+    /// What the `index`th root is colored in the current palette.
+    pub fn root_color(&self, index: usize) -> (u8, u8, u8) {
+        let colors = PALETTES[self.palette].1;
+        colors[index % colors.len()]
+    }
+
+    fn colorize(&mut self) {
+        let colors = PALETTES[self.palette].1;
 
         /*let num_colors = roots.len();
         let mut colors: Vec<(u8, u8, u8)> = Vec::with_capacity(num_colors);
@@ -179,53 +470,418 @@ impl Fractal {
         }*/
 
         let mut pixel_index = 0;
-        for c in &candidates {
-            match c {
-                None => {
-                    self.pixels[pixel_index    ] = 0;
-                    self.pixels[pixel_index + 1] = 0;
-                    self.pixels[pixel_index + 2] = 0;
-                },
-                Some((root_index, iters)) => {
-                    let dist = (-4f64 * (*iters as f64) / (Self::MAX_ITERS as f64)).exp();
+        for c in &self.candidates {
+            let (r, g, b) = shade(colors, *c).unwrap_or((0, 0, 0));
 
-                    let color = colors[*root_index];
+            self.pixels[pixel_index    ] = r;
+            self.pixels[pixel_index + 1] = g;
+            self.pixels[pixel_index + 2] = b;
 
-                    self.pixels[pixel_index    ] = (dist*(color.0 as f64)) as u8;
-                    self.pixels[pixel_index + 1] = (dist*(color.1 as f64)) as u8;
-                    self.pixels[pixel_index + 2] = (dist*(color.2 as f64)) as u8;
+            self.pixels[pixel_index + 3] = 255; // alpha
+
+            pixel_index += 4;
+        }
+
+        for (index, samples) in &self.supersamples {
+            self.pixels[index*4..][..4].copy_from_slice(&average(colors, samples, Background::Opaque));
+        }
+
+        self.generation = next_generation();
+    }
+
+    /// Where the sample for cell `(x, y)` of a grid with one sample per `scale`x`scale` block lies.
+    fn start<T: SimdFloat>(&self, scale: usize, (x, y): (usize, usize)) -> Complex<T> {
+        let x_scale = (self.view.right-self.view.left) / (self.size.0 as f64);
+        let y_scale = (self.view.bottom-self.view.top) / (self.size.1 as f64);
+
+        Complex::new(
+            T::from(((x*scale) as f64)*x_scale + self.view.left).unwrap(),
+            T::from(((y*scale) as f64)*y_scale + self.view.top).unwrap()
+        )
+    }
+
+    /// Samples the pixels `supersampling` picks out again, a row at a time. `candidates` are
+    /// what the pixels themselves went to.
+    fn supersample<T: SimdFloat>(
+        &mut self,
+        newton: &Newton<T>,
+        supersampling: Supersampling,
+        candidates: &[Option<(usize, u32)>],
+        mut keep_going: impl FnMut(f64) -> bool
+    ) -> Option<Supersamples> {
+        let (width, height) = self.size;
+        let root = |x: usize, y: usize| candidates[y*width + x].map(|(root, _)| root);
+
+        // anything on the way to a different root than one of the eight around it
+        let on_edge = |x: usize, y: usize| {
+            let (left, right) = (x.saturating_sub(1), (x + 1).min(width - 1));
+            let (top, bottom) = (y.saturating_sub(1), (y + 1).min(height - 1));
+
+            (top..=bottom).any(|j| (left..=right).any(|i| root(i, j) != root(x, y)))
+        };
+
+        let mut supersamples = Vec::new();
+        for y in 0..height {
+            let pixels: Vec<usize> = (0..width).filter(|x| !supersampling.adaptive || on_edge(*x, y)).collect();
+
+            // centered on where the pixel's own sample was
+            let fractal = &*self;
+            let starts: Vec<Complex<T>> = pixels.iter()
+                .flat_map(|x| {
+                    let offsets = supersampling.offsets((*x, y));
+                    offsets.into_iter().map(move |(dx, dy)| fractal.point_at((*x as f64 + dx - 0.5f64, y as f64 + dy - 0.5f64)))
+                })
+                .map(|point| Complex::new(T::from(point.re).unwrap(), T::from(point.im).unwrap()))
+                .collect();
+
+            let found = self.classify(newton, &starts);
+            for (x, samples) in pixels.iter().zip(found.chunks(supersampling.size*supersampling.size)) {
+                supersamples.push((y*width + x, samples.to_vec()));
+            }
+
+            if !keep_going(((y + 1) as f64)/(height as f64)) {
+                return None;
+            }
+        }
+
+        Some(supersamples)
+    }
+
+    /// Finds which root each of `starts` goes to, and how quickly.
+    fn classify<T: SimdFloat>(&mut self, newton: &Newton<T>, starts: &[Complex<T>]) -> Vec<Option<(usize, u32)>> {
+        let found = match self.kernel {
+            Kernel::Scalar => starts.iter().map(|s| newton.get_root(*s)).collect(),
+            Kernel::Simd => T::get_roots(newton, starts),
+        };
+
+        let mut candidates = Vec::with_capacity(found.len());
+        for candidate in found {
+            let candidate = candidate.map(|(root, iters)| (Complex::new(root.re.to_f64().unwrap(), root.im.to_f64().unwrap()), iters));
+
+            if let Some((root, iters)) = candidate {
+                let mut exists = false;
+                for (index, r) in self.roots.iter().enumerate() {
+                    if (root-r).norm_sqr() < 4f64*Self::TOLERANCE*Self::TOLERANCE {
+                        exists = true;
+                        candidates.push(Some((index, iters))); // this root already found
+                        break;
+                    }
+                }
+
+                if !exists {
+                    candidates.push(Some((self.roots.len(), iters))); // this root is new
+                    self.roots.push(root);
                 }
+            } else {
+                candidates.push(None); // doesn't converge
             }
+        }
 
-            self.pixels[pixel_index + 3] = 1; // alpha
+        candidates
+    }
 
-            pixel_index += 4;
+    fn sample_brute_force<T: SimdFloat>(
+        &mut self,
+        newton: &Newton<T>,
+        scale: usize,
+        grid_size: (usize, usize),
+        mut keep_going: impl FnMut(f64) -> bool
+    ) -> Option<Vec<Option<(usize, u32)>>> {
+        let mut grid = Vec::with_capacity(grid_size.0*grid_size.1);
+
+        for y in 0..grid_size.1 {
+            let starts: Vec<Complex<T>> = (0..grid_size.0).map(|x| self.start(scale, (x, y))).collect();
+            grid.extend(self.classify(newton, &starts));
+
+            if !keep_going(((y+1) as f64) / (grid_size.1 as f64)) {
+                return None;
+            }
+        }
+
+        Some(grid)
+    }
+
+    /// Mariani-Silver: a rectangle whose whole border goes to the same root gets its inside
+    /// filled in without sampling it, with the iteration counts blended in from the border,
+    /// and anything else is cut in four. Works down the grid in bands so `keep_going` still
+    /// gets asked regularly.
+    fn sample_subdivided<T: SimdFloat>(
+        &mut self,
+        newton: &Newton<T>,
+        scale: usize,
+        grid_size: (usize, usize),
+        mut keep_going: impl FnMut(f64) -> bool
+    ) -> Option<Vec<Option<(usize, u32)>>> {
+        const BAND: usize = 64;
+
+        if grid_size.0 == 0 || grid_size.1 == 0 {
+            return Some(Vec::new());
+        }
+
+        let mut grid = vec![None; grid_size.0*grid_size.1];
+
+        for top in (0..grid_size.1).step_by(BAND) {
+            let bottom = (top + BAND).min(grid_size.1) - 1;
+            self.subdivide(newton, scale, grid_size, &mut grid, (0, top, grid_size.0 - 1, bottom));
+
+            if !keep_going(((bottom+1) as f64) / (grid_size.1 as f64)) {
+                return None;
+            }
         }
 
-        println!("texture: {}", Instant::now().duration_since(beginning).as_micros());
+        Some(grid.into_iter().map(|cell| cell.expect("every cell sampled or filled")).collect())
     }
+
+    /// `(left, top, right, bottom)` are inclusive grid coordinates.
+    fn subdivide<T: SimdFloat>(
+        &mut self,
+        newton: &Newton<T>,
+        scale: usize,
+        grid_size: (usize, usize),
+        grid: &mut [Option<Option<(usize, u32)>>],
+        (left, top, right, bottom): (usize, usize, usize, usize)
+    ) {
+        const SMALLEST: usize = 4;
+
+        let mut border: Vec<(usize, usize)> = Vec::new();
+        for x in left..=right {
+            border.push((x, top));
+            if bottom != top {
+                border.push((x, bottom));
+            }
+        }
+        for y in top+1..bottom {
+            border.push((left, y));
+            if right != left {
+                border.push((right, y));
+            }
+        }
+        self.sample_cells(newton, scale, grid_size, grid, &border);
+
+        if right - left < 2 || bottom - top < 2 {
+            return; // all border, no inside
+        }
+
+        let inside: Vec<(usize, usize)> = (top+1..bottom).flat_map(|y| (left+1..right).map(move |x| (x, y))).collect();
+
+        let root = |cell: &Option<Option<(usize, u32)>>| cell.map(|candidate| candidate.map(|(root, _)| root));
+        let first = root(&grid[top*grid_size.0 + left]);
+
+        if border.iter().all(|(x, y)| root(&grid[y*grid_size.0 + x]) == first) {
+            // the iteration counts still vary inside, so blend them in from the border
+            let iters = |x: usize, y: usize| grid[y*grid_size.0 + x].flatten().map_or(0f64, |(_, iters)| iters as f64);
+
+            let filled: Vec<Option<(usize, u32)>> = inside.iter()
+                .map(|&(x, y)| {
+                    let across = ((x - left) as f64)/((right - left) as f64);
+                    let down = ((y - top) as f64)/((bottom - top) as f64);
+
+                    let horizontal = iters(left, y)*(1f64 - across) + iters(right, y)*across;
+                    let vertical = iters(x, top)*(1f64 - down) + iters(x, bottom)*down;
+
+                    first.flatten().map(|root| (root, ((horizontal + vertical)/2f64).round() as u32))
+                })
+                .collect();
+
+            for ((x, y), candidate) in inside.into_iter().zip(filled) {
+                grid[y*grid_size.0 + x] = Some(candidate);
+            }
+        } else if right - left <= SMALLEST && bottom - top <= SMALLEST {
+            self.sample_cells(newton, scale, grid_size, grid, &inside);
+        } else {
+            let (middle_x, middle_y) = ((left + right)/2, (top + bottom)/2);
+
+            self.subdivide(newton, scale, grid_size, grid, (left, top, middle_x, middle_y));
+            self.subdivide(newton, scale, grid_size, grid, (middle_x, top, right, middle_y));
+            self.subdivide(newton, scale, grid_size, grid, (left, middle_y, middle_x, bottom));
+            self.subdivide(newton, scale, grid_size, grid, (middle_x, middle_y, right, bottom));
+        }
+    }
+
+    /// Samples whichever of `cells` haven't been sampled yet, all in one go.
+    fn sample_cells<T: SimdFloat>(
+        &mut self,
+        newton: &Newton<T>,
+        scale: usize,
+        grid_size: (usize, usize),
+        grid: &mut [Option<Option<(usize, u32)>>],
+        cells: &[(usize, usize)]
+    ) {
+        let missing: Vec<(usize, usize)> = cells.iter().copied().filter(|(x, y)| grid[y*grid_size.0 + x].is_none()).collect();
+        let starts: Vec<Complex<T>> = missing.iter().map(|cell| self.start(scale, *cell)).collect();
+
+        for ((x, y), candidate) in missing.iter().zip(self.classify(newton, &starts)) {
+            grid[y*grid_size.0 + x] = Some(candidate);
+        }
+    }
+
+    /// Renders the current view by brute force and by subdivision, returning the pixels
+    /// the two send to different roots.
+    pub fn verify_subdivision(&self) -> Vec<(usize, usize)> {
+        let mut brute_force = self.clone();
+        brute_force.sampling = Sampling::BruteForce;
+        brute_force.supersampling = None;
+        brute_force.generate();
+
+        let mut subdivided = self.clone();
+        subdivided.sampling = Sampling::Subdivision;
+        subdivided.supersampling = None;
+        subdivided.generate();
+
+        // each of them numbers the roots in the order it happened to find them
+        let same = |a: &Option<(usize, u32)>, b: &Option<(usize, u32)>| match (a, b) {
+            (Some((root_a, _)), Some((root_b, _))) =>
+                (brute_force.roots[*root_a]-subdivided.roots[*root_b]).norm_sqr() < 4f64*Self::TOLERANCE*Self::TOLERANCE,
+            (None, None) => true,
+            _ => false,
+        };
+
+        brute_force.candidates.iter()
+            .zip(&subdivided.candidates)
+            .enumerate()
+            .filter(|(_, (a, b))| !same(a, b))
+            .map(|(i, _)| (i % self.size.0, i / self.size.0))
+            .collect()
+    }
+
+    /// Newton's method on the current polynomial in `T`, for finding roots one point at a time.
+    pub fn newton<T: Float>(&self) -> Newton<T> {
+        Newton::new(&self.coefficients, Self::TOLERANCE)
+    }
+
+    /// The path Newton's method takes from `start`, and where it leads.
+    pub fn orbit(&self, start: Complex<f64>) -> Orbit {
+        let points = Newton::<f64>::new(&self.coefficients, Self::TOLERANCE).orbit(start);
+        let close = |a: Complex<f64>, b: Complex<f64>| (a-b).norm_sqr() < 4f64*Self::TOLERANCE*Self::TOLERANCE;
+
+        let steps = points.len() - 1;
+        let last = points[steps];
+
+        let outcome = if (last-points[steps-1]).norm_sqr() < Self::TOLERANCE*Self::TOLERANCE {
+            Outcome::Converged {
+                root: self.roots.iter().position(|root| close(*root, last)),
+                iters: (steps - 1) as u32,
+            }
+        } else if let Some(period) = (2..steps).find(|period| close(points[steps-period], last)) {
+            Outcome::Cycle { period }
+        } else {
+            Outcome::Unsettled
+        };
+
+        Orbit { points, outcome }
+    }
+}
+
+/// Newton's method on a polynomial, carried out in the float type `T`.
+pub struct Newton<T> {
+    pub(crate) coefficients: Vec<Complex<T>>,
+    pub(crate) derivative: Vec<Complex<T>>,
+    pub(crate) tolerance: T,
 }
 
-impl Drawable for Fractal {
-    fn draw(&self, ctx: &mut Context, param: DrawParam) -> GameResult {
-        let image = Image::from_rgba8(ctx, self.size.0 as u16, self.size.1 as u16, &self.pixels).expect("fractal image");
-        graphics::draw(ctx, &image, param)
+impl<T: Float> Newton<T> {
+    fn new(coefficients: &[Complex<f64>], tolerance: f64) -> Newton<T> {
+        let convert = |c: &Complex<f64>| Complex::new(T::from(c.re).unwrap(), T::from(c.im).unwrap());
+
+        let coefficients: Vec<Complex<T>> = coefficients.iter().map(convert).collect();
+        let derivative = coefficients.iter()
+            .enumerate()
+            .skip(1)
+            .map(|(i, c)| c*T::from(i).unwrap())
+            .collect();
+
+        Newton {
+            coefficients,
+            derivative,
+            tolerance: T::from(tolerance).unwrap(),
+        }
     }
 
-    fn dimensions(&self, _ctx: &mut Context) -> Option<Rect> {
-        Some(Rect::new(0f32, 0f32, self.size.0 as f32, self.size.1 as f32))
+    fn evaluate(polynomial: &[Complex<T>], z: Complex<T>) -> Complex<T> {
+        polynomial.iter().rev().fold(Complex::new(T::zero(), T::zero()), |result, c| result*z + c)
     }
 
-    fn set_blend_mode(&mut self, _mode: Option<BlendMode>) {
-        // TODO: figure out what to do here
+    /// `s` followed by every iterate up to where `get_root` would stop.
+    pub fn orbit(&self, s: Complex<T>) -> Vec<Complex<T>> {
+        let mut orbit = vec![s];
+
+        for _ in 0..Fractal::MAX_ITERS {
+            let z_prev = orbit[orbit.len()-1];
+            let z = z_prev - Self::evaluate(&self.coefficients, z_prev) / Self::evaluate(&self.derivative, z_prev);
+            orbit.push(z);
+
+            if (z-z_prev).norm_sqr() < self.tolerance*self.tolerance {
+                break;
+            }
+        }
+
+        orbit
     }
 
-    fn blend_mode(&self) -> Option<BlendMode> {
-        // TODO: figure out what to do here
+    pub fn get_root(&self, s: Complex<T>) -> Option<(Complex<T>, u32)> {
+        let mut z = s;
+        let mut z_prev;
+
+        for i in 0..Fractal::MAX_ITERS {
+            z_prev = z;
+            z = z - Self::evaluate(&self.coefficients, z) / Self::evaluate(&self.derivative, z);
+
+            if (z-z_prev).norm_sqr() < self.tolerance*self.tolerance {
+                return Some((z, i));
+            }
+        }
+
         None
     }
 }
 
+/// The color a single sample gets, which fades the longer it took to converge.
+fn shade(colors: &[(u8, u8, u8)], candidate: Option<(usize, u32)>) -> Option<(u8, u8, u8)> {
+    candidate.map(|(root_index, iters)| {
+        let dist = (-4f64 * (iters as f64) / (Fractal::MAX_ITERS as f64)).exp();
+        let color = colors[root_index % colors.len()];
+
+        ((dist*(color.0 as f64)) as u8, (dist*(color.1 as f64)) as u8, (dist*(color.2 as f64)) as u8)
+    })
+}
+
+fn to_linear(channel: u8) -> f64 {
+    let c = (channel as f64)/255f64;
+    if c <= 0.04045f64 { c/12.92f64 } else { ((c + 0.055f64)/1.055f64).powf(2.4f64) }
+}
+
+fn from_linear(c: f64) -> u8 {
+    let c = if c <= 0.0031308f64 { 12.92f64*c } else { 1.055f64*c.powf(1f64/2.4f64) - 0.055f64 };
+    (c*255f64).round().clamp(0f64, 255f64) as u8
+}
+
+/// The RGBA pixel a set of samples averages out to, in linear light so edges don't come out
+/// darker than either side. Against a transparent `background` the ones that don't converge
+/// only take away from the alpha.
+fn average(colors: &[(u8, u8, u8)], samples: &[Option<(usize, u32)>], background: Background) -> [u8; 4] {
+    let shaded: Vec<(u8, u8, u8)> = samples.iter().filter_map(|sample| shade(colors, *sample)).collect();
+
+    let counted = match background {
+        Background::Opaque => samples.len(),
+        Background::Transparent if shaded.is_empty() => return [0, 0, 0, 0],
+        Background::Transparent => shaded.len(),
+    };
+
+    let mut sum = [0f64; 3];
+    for (r, g, b) in &shaded {
+        sum[0] += to_linear(*r);
+        sum[1] += to_linear(*g);
+        sum[2] += to_linear(*b);
+    }
+
+    let alpha = match background {
+        Background::Opaque => 255,
+        Background::Transparent => ((shaded.len() as f64)*255f64/(samples.len() as f64)).round() as u8,
+    };
+
+    [from_linear(sum[0]/(counted as f64)), from_linear(sum[1]/(counted as f64)), from_linear(sum[2]/(counted as f64)), alpha]
+}
+
 #[allow(dead_code)]
 #[allow(non_snake_case)]
 fn hsv_to_rgb(hsv: (f64, f64, f64)) -> (u8, u8, u8) {
@@ -259,3 +915,467 @@ fn hsv_to_rgb(hsv: (f64, f64, f64)) -> (u8, u8, u8) {
 
     (color.0 as u8, color.1 as u8, color.2 as u8)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::testing::{cycle, temp_path, unity};
+
+    // z^3 - 1 with the default view, small enough to render quickly
+    fn cubic() -> Fractal {
+        Fractal::with_coefficients(
+            (160, 160),
+            Rectangle { left: -5f64, top: -5f64, right: 5f64, bottom: 5f64 },
+            unity(3)
+        )
+    }
+
+    // a lopsided quintic on a width that doesn't divide into whole groups of lanes
+    fn quintic() -> Fractal {
+        Fractal::with_coefficients(
+            (157, 120),
+            Rectangle { left: -3f64, top: -2f64, right: 2f64, bottom: 2f64 },
+            vec![
+                Complex::new(-0.2796455185190574, -8.619337302126723),
+                Complex::new(7.591418031049244, 4.167755685364256),
+                Complex::new(-9.121138413779903, -6.79613957297315),
+                Complex::new(9.197246762941262, 8.190568781916397),
+                Complex::new(5.366325985514713, -1.1587722090698378),
+                Complex::new(1f64, 0f64),
+            ]
+        )
+    }
+
+    fn assert_kernels_agree<T: SimdFloat>() {
+        let mut scalar = quintic();
+        let mut simd = quintic();
+
+        scalar.set_kernel(Kernel::Scalar);
+        simd.set_kernel(Kernel::Simd);
+
+        scalar.generate_as::<T>();
+        simd.generate_as::<T>();
+
+        assert_eq!(scalar.roots, simd.roots);
+        assert!(scalar.candidates == simd.candidates);
+        assert_eq!(scalar.pixels, simd.pixels);
+    }
+
+    #[test]
+    fn orbits_end_where_get_root_does() {
+        let mut fractal = quintic();
+        fractal.generate();
+
+        let newton = Newton::<f64>::new(&fractal.coefficients, Fractal::TOLERANCE);
+        for start in [Complex::new(-2.5f64, 1.5f64), Complex::new(0f64, 0f64), Complex::new(1.2f64, -0.7f64)] {
+            let orbit = fractal.orbit(start);
+            let (root, iters) = newton.get_root(start).expect("converges");
+
+            assert_eq!(orbit.points[0], start);
+            assert_eq!(orbit.points[orbit.points.len()-1], root);
+            assert!(matches!(orbit.outcome, Outcome::Converged { root: Some(_), iters: i } if i == iters));
+        }
+    }
+
+    #[test]
+    fn orbits_notice_cycles() {
+        let fractal = Fractal::with_coefficients((16, 16), Rectangle { left: -2f64, top: -2f64, right: 2f64, bottom: 2f64 }, cycle());
+
+        let orbit = fractal.orbit(Complex::new(0f64, 0f64));
+        assert_eq!(orbit.points.len(), Fractal::MAX_ITERS as usize + 1);
+        assert_eq!(orbit.outcome, Outcome::Cycle { period: 2 });
+    }
+
+    #[test]
+    fn roots_keep_their_colors_when_moved_a_little() {
+        let mut fractal = quintic();
+        fractal.generate();
+        let before = fractal.roots.clone();
+
+        let mut roots = polynomial::match_order(&before, polynomial::roots(&fractal.coefficients));
+        roots[0] += Complex::new(0.05f64, -0.05f64);
+        fractal.set_coefficients(polynomial::from_roots(fractal.coefficients[5], &roots));
+        fractal.generate();
+
+        assert_eq!(fractal.roots.len(), 5);
+        for (index, (root, old)) in fractal.roots.iter().zip(&before).enumerate() {
+            let moved = if index == 0 { 0.1f64 } else { 1e-3f64 };
+            assert!((root-old).norm() < moved, "root {} moved from {} to {}", index, old, root);
+        }
+    }
+
+    #[test]
+    fn generations_follow_the_pixels() {
+        let mut fractal = cubic();
+        let blank = fractal.generation();
+
+        fractal.generate_scaled::<f32>(4, |_| true);
+        assert_ne!(fractal.generation(), blank);
+
+        // a clone that's recolored can't be mistaken for the original
+        let mut clone = fractal.clone();
+        assert_eq!(clone.generation(), fractal.generation());
+        clone.cycle_palette();
+        fractal.cycle_palette();
+        assert_ne!(clone.generation(), fractal.generation());
+
+        let unchanged = fractal.generation();
+        fractal.set_view(Rectangle { left: -1f64, top: -1f64, right: 1f64, bottom: 1f64 });
+        fractal.set_size((160, 160));
+        assert_eq!(fractal.generation(), unchanged);
+    }
+
+    #[test]
+    fn only_the_background_is_ever_transparent() {
+        let mut fractal = Fractal::with_coefficients((64, 64), Rectangle { left: -0.5f64, top: -0.5f64, right: 0.5f64, bottom: 0.5f64 }, cycle());
+        fractal.generate();
+
+        assert!(fractal.pixels.chunks_exact(4).all(|pixel| pixel[3] == 255));
+
+        let transparent = fractal.pixels_with(Background::Transparent);
+        let mut background = 0;
+        for ((pixel, candidate), opaque) in transparent.chunks_exact(4).zip(&fractal.candidates).zip(fractal.pixels.chunks_exact(4)) {
+            assert_eq!(pixel[..3], opaque[..3]);
+            assert_eq!(pixel[3], if candidate.is_none() { 0 } else { 255 });
+            background += candidate.is_none() as usize;
+        }
+        assert!(background > 0);
+    }
+
+    #[test]
+    fn seeds_always_give_the_same_polynomial() {
+        let view = Rectangle { left: -5f64, top: -5f64, right: 5f64, bottom: 5f64 };
+        let random = Random::default();
+        let a = Fractal::with_seed((8, 8), view, &random, 1234);
+        let b = Fractal::with_seed((8, 8), view, &random, 1234);
+
+        assert_eq!(a.coefficients(), b.coefficients());
+        assert_eq!(a.coefficients().len(), 5);
+        assert_ne!(a.coefficients(), Fractal::with_seed((8, 8), view, &random, 1235).coefficients());
+
+        // going back to a seed is going back to its polynomial, but moving anything loses it
+        let mut c = Fractal::with_seed((8, 8), view, &random, 99);
+        c.set_seed(&random, 1234);
+        assert_eq!((c.coefficients(), c.seed()), (a.coefficients(), Some(1234)));
+        c.set_coefficients(a.coefficients().to_vec());
+        assert_eq!(c.seed(), None);
+    }
+
+    #[test]
+    fn seeds_look_the_same_however_they_got_there() {
+        let view = Rectangle { left: -5f64, top: -5f64, right: 5f64, bottom: 5f64 };
+        let random = Random::default();
+
+        let mut fresh = Fractal::with_seed((64, 64), view, &random, 1234);
+        fresh.generate();
+
+        let mut switched = Fractal::with_seed((64, 64), view, &random, 99);
+        switched.generate();
+        switched.set_seed(&random, 1234);
+        switched.generate();
+
+        assert!(fresh.pixels() == switched.pixels());
+    }
+
+    #[test]
+    fn saved_images_remember_the_seed() {
+        let path = temp_path("seed.png");
+        let path = path.as_str();
+
+        let random = Random { degree: 3, ..Random::default() };
+        let mut fractal = Fractal::with_seed((16, 16), Rectangle { left: -5f64, top: -5f64, right: 5f64, bottom: 5f64 }, &random, 42);
+        fractal.generate();
+        fractal.save_to_path(path, SaveOptions { background: Background::Opaque, grid: false }).expect("saved image");
+
+        let decoder = png::Decoder::new(File::open(path).expect("saved image"));
+        let reader = decoder.read_info().expect("png header");
+        let text: Vec<(String, String)> = reader.info().uncompressed_latin1_text.iter()
+            .map(|chunk| (chunk.keyword.clone(), chunk.text.clone()))
+            .collect();
+        std::fs::remove_file(path).expect("removed image");
+
+        assert!(text.contains(&(String::from("Seed"), String::from("42"))), "{:?}", text);
+        assert!(text.iter().any(|(keyword, _)| keyword == "Coefficients"));
+    }
+
+    #[test]
+    fn saving_somewhere_that_isnt_there_fails() {
+        let fractal = Fractal::with_coefficients((4, 4), Rectangle { left: -2f64, top: -2f64, right: 2f64, bottom: 2f64 }, unity(3));
+        let path = temp_path("missing/image.png");
+
+        assert!(fractal.save_to_path(&path, SaveOptions { background: Background::Opaque, grid: false }).is_err());
+    }
+
+    #[test]
+    fn adaptive_supersampling_only_refines_edges() {
+        let mut fractal = cubic();
+        fractal.set_supersampling(Some(Supersampling { pattern: Pattern::Grid, size: 3, adaptive: true }));
+        fractal.generate();
+
+        let (width, height) = fractal.size;
+        let refined = fractal.supersamples.len();
+        assert!(refined > 0 && refined < width*height/4, "{} of {} pixels refined", refined, width*height);
+
+        let root = |i: usize| fractal.candidates[i].map(|(root, _)| root);
+        for (index, samples) in &fractal.supersamples {
+            assert_eq!(samples.len(), 9);
+
+            let (x, y) = ((index % width) as i64, (index / width) as i64);
+            let neighbours = (-1..=1).flat_map(|j| (-1..=1).map(move |i| (x + i, y + j)))
+                .filter(|(x, y)| *x >= 0 && *y >= 0 && *x < width as i64 && *y < height as i64)
+                .map(|(x, y)| (y as usize)*width + x as usize);
+            assert!(neighbours.into_iter().any(|i| root(i) != root(*index)));
+        }
+
+        // and nothing's left over from it once it's turned off
+        fractal.set_supersampling(None);
+        fractal.generate();
+        assert!(fractal.supersamples.is_empty());
+    }
+
+    #[test]
+    fn rotated_grids_never_share_a_row_or_column() {
+        for size in 2..5 {
+            let offsets = Supersampling { pattern: Pattern::RotatedGrid, size, adaptive: false }.offsets((3, 5));
+
+            for (i, a) in offsets.iter().enumerate() {
+                assert!(0f64 < a.0 && a.0 < 1f64 && 0f64 < a.1 && a.1 < 1f64);
+                for b in &offsets[i+1..] {
+                    assert!(a.0 != b.0 && a.1 != b.1, "{:?} and {:?}", a, b);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn jitter_is_different_in_every_pixel() {
+        let jittered = Supersampling { pattern: Pattern::Jittered, size: 3, adaptive: false };
+
+        assert_eq!(jittered.offsets((4, 7)), jittered.offsets((4, 7)));
+        assert_ne!(jittered.offsets((4, 7)), jittered.offsets((5, 7)));
+        assert_ne!(jittered.offsets((4, 7)), jittered.offsets((4, 8)));
+    }
+
+    #[test]
+    fn supersampling_reports_its_own_progress() {
+        let mut fractal = cubic();
+        fractal.set_supersampling(Some(Supersampling { pattern: Pattern::Grid, size: 2, adaptive: true }));
+
+        let mut reported = Vec::new();
+        fractal.generate_scaled::<f64>(1, |done| { reported.push(done); true }).expect("generated");
+
+        // one for every row of each half, going steadily up to the end
+        assert_eq!(reported.len(), 2*fractal.size.1);
+        assert_eq!(reported[fractal.size.1 - 1], 0.5f64);
+        assert_eq!(reported.last(), Some(&1f64));
+        assert!(reported.windows(2).all(|pair| pair[0] < pair[1]));
+
+        // and stopping it partway through supersampling stops it
+        let mut rows = 0;
+        assert!(fractal.generate_scaled::<f64>(1, |done| { rows += 1; done < 0.75f64 }).is_none());
+        assert!(rows < 2*fractal.size.1);
+    }
+
+    #[test]
+    fn samples_are_averaged_in_linear_light() {
+        let colors = [(255, 255, 255)];
+        let half = [Some((0, 0)), None];
+
+        // half white and half black is a lot brighter than 128 once it's back in sRGB
+        assert_eq!(average(&colors, &half, Background::Opaque), [188, 188, 188, 255]);
+        assert_eq!(average(&colors, &half, Background::Transparent), [255, 255, 255, 128]);
+        assert_eq!(average(&colors, &[None, None], Background::Transparent), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn simd_kernel_matches_scalar_in_f64() {
+        assert_kernels_agree::<f64>();
+    }
+
+    #[test]
+    fn simd_kernel_matches_scalar_in_f32() {
+        assert_kernels_agree::<f32>();
+    }
+
+    #[test]
+    fn subdivision_matches_brute_force() {
+        for fractal in [cubic(), quintic()] {
+            let mismatched = fractal.verify_subdivision();
+            let total = fractal.size.0*fractal.size.1;
+            assert!((mismatched.len() as f64) < 0.001*(total as f64), "{} of {} pixels mismatched", mismatched.len(), total);
+        }
+    }
+
+    #[test]
+    fn empty_images_generate_nothing() {
+        for sampling in [Sampling::BruteForce, Sampling::Subdivision] {
+            for size in [(0, 10), (10, 0), (0, 0)] {
+                let mut fractal = cubic();
+                fractal.set_sampling(sampling);
+                fractal.set_size(size);
+                fractal.generate();
+
+                assert!(fractal.pixels.is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn f32_finds_the_same_roots_as_f64() {
+        let mut single = cubic();
+        let mut double = cubic();
+
+        single.generate_as::<f32>();
+        double.generate_as::<f64>();
+
+        assert_eq!(single.roots.len(), double.roots.len());
+        for root in &single.roots {
+            let closest = double.roots.iter().map(|r| (root-r).norm()).fold(f64::INFINITY, f64::min);
+            assert!(closest < 1e-3, "f32 root {} has no f64 counterpart", root);
+        }
+    }
+
+    #[test]
+    fn f32_classifies_nearly_every_pixel_like_f64() {
+        let mut single = cubic();
+        let mut double = cubic();
+
+        single.generate_as::<f32>();
+        double.generate_as::<f64>();
+
+        let mut mismatched = 0;
+        let mut shaded_differently = 0;
+        for (a, b) in single.candidates.iter().zip(&double.candidates) {
+            match (a, b) {
+                (Some((root_a, iters_a)), Some((root_b, iters_b))) => {
+                    if (single.roots[*root_a]-double.roots[*root_b]).norm() > 1e-3 {
+                        mismatched += 1;
+                    }
+                    if iters_a != iters_b {
+                        shaded_differently += 1;
+                    }
+                },
+                (None, None) => {},
+                _ => mismatched += 1,
+            }
+        }
+
+        let total = single.candidates.len();
+        assert!((mismatched as f64) < 0.001*(total as f64), "{} of {} pixels classified differently", mismatched, total);
+        assert!((shaded_differently as f64) < 0.01*(total as f64), "{} of {} pixels shaded differently", shaded_differently, total);
+    }
+
+    // 64 bit FNV-1a, which is plenty to notice an image changing
+    fn fnv(bytes: &[u8]) -> u64 {
+        bytes.iter().fold(0xcbf29ce484222325u64, |hash, byte| (hash ^ (*byte as u64)).wrapping_mul(0x100000001b3))
+    }
+
+    #[test]
+    fn scaling_the_view_zooms_into_that_part_of_it() {
+        let mut fractal = cubic();
+
+        fractal.scale_view(Rectangle { left: 0f64, top: 0f64, right: 1f64, bottom: 1f64 });
+        assert_eq!(fractal.view, Rectangle { left: -5f64, top: -5f64, right: 5f64, bottom: 5f64 });
+
+        fractal.scale_view(Rectangle { left: 0.25f64, top: 0.5f64, right: 0.75f64, bottom: 1f64 });
+        assert_eq!(fractal.view, Rectangle { left: -2.5f64, top: 0f64, right: 2.5f64, bottom: 5f64 });
+
+        // and again, relative to where it is now
+        fractal.scale_view(Rectangle { left: 0.5f64, top: 0f64, right: 1f64, bottom: 0.2f64 });
+        assert_eq!(fractal.view, Rectangle { left: 0f64, top: 0f64, right: 2.5f64, bottom: 1f64 });
+    }
+
+    #[test]
+    fn get_root_finds_roots_of_unity() {
+        for n in 2..8 {
+            let newton = Newton::<f64>::new(&unity(n), Fractal::TOLERANCE);
+
+            // a little way out from each root, which should lead back to it
+            for k in 0..n {
+                let expected = Complex::from_polar(1f64, 2f64*std::f64::consts::PI*(k as f64)/(n as f64));
+                let start = expected*Complex::from_polar(1.3f64, 0.1f64);
+
+                let (root, iters) = newton.get_root(start).expect("converges");
+                assert!((root - expected).norm() < 1e-6, "z^{} - 1 from {}: {}", n, start, root);
+                assert!(iters < Fractal::MAX_ITERS);
+            }
+
+            // the derivative's zero there, so it never gets anywhere
+            assert_eq!(newton.get_root(Complex::new(0f64, 0f64)), None);
+        }
+    }
+
+    #[test]
+    fn each_root_is_only_found_once() {
+        for n in [3, 5, 8] {
+            let mut fractal = Fractal::with_coefficients((64, 64), Rectangle { left: -2f64, top: -2f64, right: 2f64, bottom: 2f64 }, unity(n));
+            fractal.generate();
+
+            assert_eq!(fractal.roots.len(), n, "z^{} - 1: {:?}", n, fractal.roots);
+            for (i, a) in fractal.roots.iter().enumerate() {
+                for b in &fractal.roots[i+1..] {
+                    assert!((a - b).norm() > 0.1f64, "{} and {}", a, b);
+                }
+            }
+            assert!(fractal.candidates.iter().flatten().all(|(root, _)| *root < n));
+        }
+    }
+
+    #[test]
+    fn pixels_that_never_converge_are_black() {
+        let mut fractal = Fractal::with_coefficients((20, 20), Rectangle { left: -1f64, top: -1f64, right: 1f64, bottom: 1f64 }, cycle());
+        fractal.generate();
+
+        // pixel (10, 10) is sampled right at 0
+        let index = 10*20 + 10;
+        assert_eq!(fractal.candidates[index], None);
+        assert_eq!(fractal.pixels[index*4..][..4], [0, 0, 0, 255]);
+        assert_eq!(fractal.pixels_with(Background::Transparent)[index*4..][..4], [0, 0, 0, 0]);
+        assert_eq!(shade(PALETTES[0].1, None), None);
+    }
+
+    #[test]
+    fn hsv_converts_to_rgb() {
+        assert_eq!(hsv_to_rgb((0f64, 1f64, 255f64)), (255, 0, 0));
+        assert_eq!(hsv_to_rgb((0.25f64, 1f64, 255f64)), (127, 255, 0));
+        assert_eq!(hsv_to_rgb((0.5f64, 1f64, 255f64)), (0, 255, 255));
+        assert_eq!(hsv_to_rgb((0.75f64, 1f64, 255f64)), (127, 0, 255));
+
+        // hues wrap around, and no saturation is grey
+        assert_eq!(hsv_to_rgb((1f64, 1f64, 255f64)), (255, 0, 0));
+        assert_eq!(hsv_to_rgb((0.3f64, 0f64, 200f64)), (200, 200, 200));
+        assert_eq!(hsv_to_rgb((0.5f64, 0.5f64, 200f64)), (100, 200, 200));
+    }
+
+    // If these change on purpose, e.g. after changing the palettes, the new hashes are in the message.
+    #[test]
+    fn golden_images() {
+        let view = Rectangle { left: -2f64, top: -2f64, right: 2f64, bottom: 2f64 };
+
+        let mut cubic = Fractal::with_coefficients((48, 48), view, unity(3));
+        let mut quintic = quintic();
+        let mut seeded = Fractal::with_seed((64, 40), view, &Random { degree: 6, ..Random::default() }, 7);
+        let mut supersampled = Fractal::with_coefficients((48, 48), view, unity(4));
+        supersampled.set_supersampling(Some(Supersampling { pattern: Pattern::RotatedGrid, size: 3, adaptive: true }));
+        let mut subdivided = Fractal::with_coefficients((48, 48), view, unity(5));
+        subdivided.set_sampling(Sampling::Subdivision);
+
+        let scenes = [
+            ("cubic", &mut cubic, 0xc556a714cf00b6a0),
+            ("quintic", &mut quintic, 0xb0716035173000c5),
+            ("seeded", &mut seeded, 0xceb7f8cff314147a),
+            ("supersampled", &mut supersampled, 0x3022927e1cfe4dcd),
+            ("subdivided", &mut subdivided, 0xc051dfbe12333a9d),
+        ];
+        let mut changed = Vec::new();
+        for (name, fractal, expected) in scenes {
+            fractal.generate();
+            let hash = fnv(&fractal.pixels);
+            if hash != expected {
+                changed.push(format!("{} is now {:#018x}", name, hash));
+            }
+        }
+        assert!(changed.is_empty(), "renders differently: {}", changed.join(", "));
+    }
+}
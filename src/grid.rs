@@ -0,0 +1,254 @@
+use rusttype::{point, Font, Scale};
+
+use crate::fractals::Rectangle;
+
+/// Roughly how many pixels apart grid lines should be.
+const SPACING: f64 = 100f64;
+const FONT_SIZE: f32 = 14f32;
+const CIRCLE_SEGMENTS: usize = 128;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    Grid,
+    Axis,
+    UnitCircle,
+}
+
+impl Kind {
+    /// White, at this opacity.
+    pub fn alpha(self) -> f32 {
+        match self {
+            Kind::Grid => 0.25f32,
+            Kind::Axis => 0.75f32,
+            Kind::UnitCircle => 0.5f32,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Line {
+    pub from: (f64, f64),
+    pub to: (f64, f64),
+    pub kind: Kind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub text: String,
+    /// Top left corner
+    pub at: (f64, f64),
+}
+
+/// The axes, unit circle and a grid spaced to suit how far in the view is, all in pixels.
+#[derive(Debug, Clone)]
+pub struct Grid {
+    pub lines: Vec<Line>,
+    pub labels: Vec<Label>,
+}
+
+/// 1, 2 or 5 times a power of ten, whichever is closest to `ideal`.
+pub fn step(ideal: f64) -> f64 {
+    let power = 10f64.powf(ideal.log10().floor());
+
+    [1f64, 2f64, 5f64, 10f64].iter()
+        .map(|m| m*power)
+        .min_by(|a, b| (a - ideal).abs().partial_cmp(&(b - ideal).abs()).expect("finite step"))
+        .expect("some step")
+}
+
+/// Every multiple of `step` from `min` to `max`.
+fn ticks(min: f64, max: f64, step: f64) -> Vec<f64> {
+    let (min, max) = (min.min(max), min.max(max));
+    let first = (min/step).ceil() as i64;
+    let last = (max/step).floor() as i64;
+
+    (first..=last).map(|i| (i as f64)*step).collect()
+}
+
+/// Enough decimals to write multiples of `step` exactly.
+fn decimals(step: f64) -> usize {
+    (-step.log10().floor()).max(0f64) as usize
+}
+
+impl Grid {
+    /// The grid over `view` shown at `size` pixels.
+    pub fn new(view: &Rectangle<f64>, size: (usize, usize)) -> Grid {
+        let (width, height) = (size.0 as f64, size.1 as f64);
+        let to_x = |re: f64| (re - view.left)*width/(view.right - view.left);
+        let to_y = |im: f64| (im - view.top)*height/(view.bottom - view.top);
+
+        let step_re = step(SPACING*(view.right - view.left).abs()/width);
+        let step_im = step(SPACING*(view.bottom - view.top).abs()/height);
+
+        // labels hug the axes while they're in sight, and the edges otherwise
+        let label_y = to_y(0f64).max(0f64).min(height - 2f64*FONT_SIZE as f64) + 2f64;
+        let label_x = to_x(0f64).max(0f64).min(width - 6f64*FONT_SIZE as f64) + 4f64;
+
+        let mut lines = Vec::new();
+        let mut labels = Vec::new();
+
+        // only what's in sight, which also keeps the unit circle sane when it's far bigger than the view
+        let mut push = |from, to, kind| {
+            if let Some((from, to)) = clip(from, to, (width, height)) {
+                lines.push(Line { from, to, kind });
+            }
+        };
+
+        for re in ticks(view.left, view.right, step_re) {
+            let x = to_x(re);
+            let kind = if re == 0f64 { Kind::Axis } else { Kind::Grid };
+            push((x, 0f64), (x, height), kind);
+            labels.push(Label { text: format!("{:.*}", decimals(step_re), re), at: (x + 4f64, label_y) });
+        }
+
+        for im in ticks(view.top, view.bottom, step_im) {
+            let y = to_y(im);
+            let kind = if im == 0f64 { Kind::Axis } else { Kind::Grid };
+            push((0f64, y), (width, y), kind);
+            if im != 0f64 {
+                labels.push(Label { text: format!("{:.*}i", decimals(step_im), im), at: (label_x, y + 2f64) });
+            }
+        }
+
+        let circle: Vec<(f64, f64)> = (0..=CIRCLE_SEGMENTS)
+            .map(|i| 2f64*std::f64::consts::PI*(i as f64)/(CIRCLE_SEGMENTS as f64))
+            .map(|angle| (to_x(angle.cos()), to_y(angle.sin())))
+            .collect();
+        for segment in circle.windows(2) {
+            push(segment[0], segment[1], Kind::UnitCircle);
+        }
+
+        Grid { lines, labels }
+    }
+
+    /// Draws the grid into RGBA `pixels` that are `size` big.
+    pub fn bake(&self, pixels: &mut [u8], size: (usize, usize)) {
+        self.bake_rows(pixels, size.0, 0);
+    }
+
+    /// Draws the grid into RGBA `pixels` that are rows `top` onwards of an image `width` across,
+    /// for when it's put together a band at a time.
+    pub fn bake_rows(&self, pixels: &mut [u8], width: usize, top: usize) {
+        let size = (width, pixels.len()/(width*4));
+        let mut blend = |x: i64, y: i64, alpha: f32| {
+            let y = y - top as i64;
+            if x < 0 || y < 0 || x >= size.0 as i64 || y >= size.1 as i64 {
+                return;
+            }
+
+            let pixel = &mut pixels[(y as usize*size.0 + x as usize)*4..][..4];
+            for channel in &mut pixel[..3] {
+                *channel = ((*channel as f32)*(1f32 - alpha) + 255f32*alpha).round() as u8;
+            }
+            pixel[3] = pixel[3].max((alpha*255f32).round() as u8);
+        };
+
+        for line in &self.lines {
+            // only the part of it in these rows, which for a band of a big image is a lot less
+            let shifted = |(x, y): (f64, f64)| (x, y - top as f64);
+            let (from, to) = match clip(shifted(line.from), shifted(line.to), (size.0 as f64, size.1 as f64)) {
+                Some(((x0, y0), (x1, y1))) => ((x0, y0 + top as f64), (x1, y1 + top as f64)),
+                None => continue,
+            };
+
+            let steps = (to.0 - from.0).abs().max((to.1 - from.1).abs()).ceil().max(1f64) as usize;
+            for i in 0..=steps {
+                let t = (i as f64)/(steps as f64);
+                let (x, y) = (from.0 + (to.0 - from.0)*t, from.1 + (to.1 - from.1)*t);
+                blend(x.floor() as i64, y.floor() as i64, line.kind.alpha());
+            }
+        }
+
+        let font = Font::try_from_bytes(include_bytes!("../res/fonts/Roboto-Regular.ttf") as &[u8]).expect("grid font");
+        let scale = Scale::uniform(FONT_SIZE);
+        let ascent = font.v_metrics(scale).ascent;
+
+        let (near, far) = (top as f64, (top + size.1) as f64);
+        for label in &self.labels {
+            if label.at.1 + 2f64*(FONT_SIZE as f64) < near || label.at.1 > far {
+                continue;
+            }
+
+            let origin = point(label.at.0 as f32, label.at.1 as f32 + ascent);
+            for glyph in font.layout(&label.text, scale, origin) {
+                if let Some(bounds) = glyph.pixel_bounding_box() {
+                    glyph.draw(|x, y, coverage| {
+                        blend(bounds.min.x as i64 + x as i64, bounds.min.y as i64 + y as i64, coverage*Kind::Axis.alpha());
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// The part of the line from `from` to `to` inside `(0, 0)`-`size`, if any (Liang-Barsky).
+fn clip(from: (f64, f64), to: (f64, f64), size: (f64, f64)) -> Option<((f64, f64), (f64, f64))> {
+    if ![from.0, from.1, to.0, to.1].iter().all(|c| c.is_finite()) {
+        return None;
+    }
+
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let (mut enter, mut exit) = (0f64, 1f64);
+
+    for (p, q) in [(-dx, from.0), (dx, size.0 - from.0), (-dy, from.1), (dy, size.1 - from.1)] {
+        if p == 0f64 {
+            if q < 0f64 {
+                return None; // parallel to this edge and outside it
+            }
+        } else {
+            let t = q/p;
+            if p < 0f64 {
+                enter = enter.max(t);
+            } else {
+                exit = exit.min(t);
+            }
+        }
+    }
+
+    if enter > exit {
+        return None;
+    }
+
+    Some(((from.0 + dx*enter, from.1 + dy*enter), (from.0 + dx*exit, from.1 + dy*exit)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steps_are_round_numbers() {
+        assert_eq!(step(1f64), 1f64);
+        assert_eq!(step(0.3f64), 0.2f64);
+        assert_eq!(step(4f64), 5f64);
+        assert_eq!(step(8f64), 10f64);
+        assert!((step(0.00037f64) - 0.0005f64).abs() < 1e-12);
+    }
+
+    #[test]
+    fn lines_are_clipped_to_the_image() {
+        assert_eq!(clip((-10f64, 5f64), (20f64, 5f64), (10f64, 10f64)), Some(((0f64, 5f64), (10f64, 5f64))));
+        assert_eq!(clip((-10f64, -5f64), (20f64, -5f64), (10f64, 10f64)), None);
+        assert_eq!(clip((0f64, f64::NAN), (1f64, 1f64), (10f64, 10f64)), None);
+    }
+
+    #[test]
+    fn axes_land_on_zero() {
+        let view = Rectangle { left: -5f64, top: -2f64, right: 5f64, bottom: 2f64 };
+        let grid = Grid::new(&view, (500, 200));
+
+        let axes: Vec<&Line> = grid.lines.iter().filter(|line| line.kind == Kind::Axis).collect();
+        assert_eq!(axes.len(), 2);
+        assert!(axes.contains(&&Line { from: (250f64, 0f64), to: (250f64, 200f64), kind: Kind::Axis }));
+        assert!(axes.contains(&&Line { from: (0f64, 100f64), to: (500f64, 100f64), kind: Kind::Axis }));
+        assert!(grid.labels.iter().any(|label| label.text == "-4"));
+        assert!(grid.labels.iter().any(|label| label.text == "2i"));
+
+        let mut pixels = vec![0u8; 500*200*4];
+        grid.bake(&mut pixels, (500, 200));
+        let at = |x: usize, y: usize| pixels[(y*500 + x)*4];
+
+        assert!(at(250, 50) > at(350, 50), "the imaginary axis stands out from the grid");
+        assert!(at(350, 50) > at(275, 50), "grid lines stand out from nothing");
+    }
+}
@@ -0,0 +1,218 @@
+use std::fs;
+
+use num::complex::Complex;
+use serde::{Deserialize, Serialize};
+
+use newtonian_rustsody::fractals::Rectangle;
+
+fn same_view(a: &Rectangle<f64>, b: &Rectangle<f64>) -> bool {
+    // a view that's been through the pixel zoom and back comes out a few bits off
+    let epsilon = 1e-9f64*(a.right - a.left).abs().max((a.bottom - a.top).abs());
+
+    (a.left - b.left).abs() <= epsilon
+        && (a.top - b.top).abs() <= epsilon
+        && (a.right - b.right).abs() <= epsilon
+        && (a.bottom - b.bottom).abs() <= epsilon
+}
+
+/// Every view generated so far, to step back and forth through like a browser's history.
+pub struct History {
+    views: Vec<Rectangle<f64>>,
+    position: usize,
+}
+
+impl History {
+    pub fn new(initial: Rectangle<f64>) -> History {
+        History {
+            views: vec![initial],
+            position: 0,
+        }
+    }
+
+    /// Moves on to `view`, forgetting anything that was undone to get here. Going to the view
+    /// that's already current changes nothing.
+    pub fn visit(&mut self, view: Rectangle<f64>) {
+        if same_view(&self.views[self.position], &view) {
+            return;
+        }
+
+        self.views.truncate(self.position + 1);
+        self.views.push(view);
+        self.position += 1;
+    }
+
+    /// Swaps the current view for `view`, keeping everything before and after it.
+    pub fn replace(&mut self, view: Rectangle<f64>) {
+        self.views[self.position] = view;
+    }
+
+    pub fn undo(&mut self) -> Option<Rectangle<f64>> {
+        if self.position == 0 {
+            return None;
+        }
+
+        self.position -= 1;
+        Some(self.views[self.position])
+    }
+
+    pub fn redo(&mut self) -> Option<Rectangle<f64>> {
+        if self.position + 1 == self.views.len() {
+            return None;
+        }
+
+        self.position += 1;
+        Some(self.views[self.position])
+    }
+
+    /// `(current, total)`, counting from 1.
+    pub fn position(&self) -> (usize, usize) {
+        (self.position + 1, self.views.len())
+    }
+}
+
+/// A spot worth coming back to: the polynomial along with where it was being looked at.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub name: String,
+    /// `(re, im)`, lowest power first
+    pub coefficients: Vec<(f64, f64)>,
+    pub view: Rectangle<f64>,
+}
+
+impl Bookmark {
+    pub fn new(name: String, coefficients: &[Complex<f64>], view: Rectangle<f64>) -> Bookmark {
+        Bookmark {
+            name,
+            coefficients: coefficients.iter().map(|c| (c.re, c.im)).collect(),
+            view,
+        }
+    }
+
+    pub fn coefficients(&self) -> Vec<Complex<f64>> {
+        self.coefficients.iter().map(|(re, im)| Complex::new(*re, *im)).collect()
+    }
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct BookmarkFile {
+    #[serde(default)]
+    bookmark: Vec<Bookmark>,
+}
+
+/// Bookmarks kept in a TOML file, written back every time one is added.
+pub struct Bookmarks {
+    path: String,
+    bookmarks: Vec<Bookmark>,
+    /// The one last jumped to
+    current: Option<usize>,
+}
+
+impl Bookmarks {
+    pub fn load(path: &str) -> Bookmarks {
+        let bookmarks = match fs::read_to_string(path) {
+            Ok(contents) => match toml::from_str::<BookmarkFile>(&contents) {
+                Ok(file) => file.bookmark,
+                Err(e) => {
+                    eprintln!("{}: {}, starting without bookmarks", path, e);
+                    Vec::new()
+                },
+            },
+            Err(_) => Vec::new(), // none saved yet
+        };
+
+        Bookmarks {
+            path: String::from(path),
+            bookmarks,
+            current: None,
+        }
+    }
+
+    pub fn add(&mut self, bookmark: Bookmark) {
+        self.bookmarks.push(bookmark);
+        self.current = Some(self.bookmarks.len() - 1);
+
+        let file = BookmarkFile { bookmark: self.bookmarks.clone() };
+        let written = toml::to_string(&file)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| fs::write(&self.path, contents).map_err(|e| e.to_string()));
+
+        if let Err(e) = written {
+            eprintln!("{}: couldn't save bookmarks, {}", self.path, e);
+        }
+    }
+
+    /// The bookmark after the one last jumped to, going round to the first after the last.
+    pub fn next(&mut self) -> Option<&Bookmark> {
+        if self.bookmarks.is_empty() {
+            return None;
+        }
+
+        let next = self.current.map_or(0, |current| (current + 1) % self.bookmarks.len());
+        self.current = Some(next);
+        Some(&self.bookmarks[next])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view(size: f64) -> Rectangle<f64> {
+        Rectangle { left: -size, top: -size, right: size, bottom: size }
+    }
+
+    #[test]
+    fn undo_and_redo_step_through_visited_views() {
+        let mut history = History::new(view(5f64));
+        history.visit(view(4f64));
+        history.visit(view(3f64));
+
+        assert_eq!(history.undo(), Some(view(4f64)));
+        assert_eq!(history.undo(), Some(view(5f64)));
+        assert_eq!(history.undo(), None);
+
+        // going back to where the history already is isn't a new step
+        history.visit(view(5f64));
+        assert_eq!(history.redo(), Some(view(4f64)));
+
+        // but going anywhere else drops what was undone
+        history.visit(view(2f64));
+        assert_eq!(history.redo(), None);
+        assert_eq!(history.position(), (3, 3));
+        assert_eq!(history.undo(), Some(view(4f64)));
+    }
+
+    #[test]
+    fn replacing_a_view_keeps_what_was_undone() {
+        let mut history = History::new(view(5f64));
+        history.visit(view(4f64));
+        history.visit(view(3f64));
+        history.undo();
+
+        history.replace(view(4.5f64));
+        assert_eq!(history.position(), (2, 3));
+        assert_eq!(history.redo(), Some(view(3f64)));
+        assert_eq!(history.undo(), Some(view(4.5f64)));
+    }
+
+    #[test]
+    fn bookmarks_survive_a_round_trip_through_the_file() {
+        let path = std::env::temp_dir().join(format!("newtonian_rustsody_{}_bookmarks.toml", std::process::id()));
+        let path = path.to_str().expect("utf-8 temp dir");
+
+        let cubic = vec![Complex::new(-1f64, 0f64), Complex::new(0f64, 0f64), Complex::new(0f64, 0f64), Complex::new(1f64, 0f64)];
+
+        let mut bookmarks = Bookmarks::load(path);
+        bookmarks.add(Bookmark::new(String::from("whole thing"), &cubic, view(5f64)));
+        bookmarks.add(Bookmark::new(String::from("middle"), &cubic, view(0.001f64)));
+
+        let mut loaded = Bookmarks::load(path);
+        fs::remove_file(path).expect("removed bookmarks");
+
+        let first = loaded.next().cloned().expect("first bookmark");
+        assert_eq!(first.name, "whole thing");
+        assert_eq!(first.coefficients(), cubic);
+        assert_eq!(loaded.next().map(|b| b.view), Some(view(0.001f64)));
+        assert_eq!(loaded.next().map(|b| b.name.as_str()), Some("whole thing"));
+    }
+}
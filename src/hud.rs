@@ -0,0 +1,201 @@
+use ggez::{Context, GameResult};
+use ggez::graphics::{self, Color, DrawMode, DrawParam, Font, Mesh, MeshBuilder, PxScale, Rect, Text, TextFragment};
+
+use num::Complex;
+
+use newtonian_rustsody::fractals::{Fractal, Orbit, Outcome, Rectangle};
+use newtonian_rustsody::grid::Grid;
+
+const MARGIN: f32 = 8f32;
+const FONT_SIZE: f32 = 16f32;
+const LABEL_SIZE: f32 = 14f32;
+
+/// Far off screen is as good as infinitely far, and a lot easier on the tessellator.
+const OFF_SCREEN: f64 = 1e5f64;
+
+/// How big the root markers are drawn, and how close the cursor has to be to grab one.
+pub const ROOT_RADIUS: f32 = 6f32;
+
+/// Text drawn over the top left corner of the window.
+pub struct Hud {
+    pub visible: bool,
+    font: Font,
+}
+
+impl Hud {
+    pub fn new(ctx: &mut Context) -> Hud {
+        let font = Font::new_glyph_font_bytes(ctx, include_bytes!("../res/fonts/Roboto-Regular.ttf")).expect("HUD font");
+
+        Hud {
+            visible: true,
+            font,
+        }
+    }
+
+    pub fn draw(&self, ctx: &mut Context, lines: &[String]) -> GameResult {
+        if !self.visible {
+            return Ok(());
+        }
+
+        let text = Text::new(TextFragment::new(lines.join("\n")).font(self.font).scale(PxScale::from(FONT_SIZE)));
+        let bounds = text.dimensions(ctx);
+
+        // darkened behind so it stays readable over the brighter palettes
+        let backdrop = Mesh::new_rectangle(
+            ctx,
+            DrawMode::fill(),
+            Rect::new(0f32, 0f32, bounds.w + 2f32*MARGIN, bounds.h + 2f32*MARGIN),
+            Color::new(0f32, 0f32, 0f32, 0.6f32)
+        )?;
+
+        graphics::draw(ctx, &backdrop, DrawParam::default())?;
+        graphics::draw(ctx, &text, DrawParam::default().dest([MARGIN, MARGIN]))
+    }
+
+    pub fn draw_grid(&self, ctx: &mut Context, grid: &Grid) -> GameResult {
+        let mut mesh = MeshBuilder::new();
+        for line in &grid.lines {
+            let points = [[line.from.0 as f32, line.from.1 as f32], [line.to.0 as f32, line.to.1 as f32]];
+            mesh.line(&points, 1f32, Color::new(1f32, 1f32, 1f32, line.kind.alpha()))?;
+        }
+
+        if !grid.lines.is_empty() {
+            let mesh = mesh.build(ctx)?;
+            graphics::draw(ctx, &mesh, DrawParam::default())?;
+        }
+
+        for label in &grid.labels {
+            let text = Text::new(TextFragment::new(label.text.as_str()).font(self.font).scale(PxScale::from(LABEL_SIZE)));
+            graphics::draw(ctx, &text, DrawParam::default().dest([label.at.0 as f32, label.at.1 as f32]))?;
+        }
+
+        Ok(())
+    }
+
+    /// A marker in its own color on every root found so far, a heavier one on `grabbed`.
+    /// `to_screen` says where in the window a point is.
+    pub fn draw_roots(
+        &self,
+        ctx: &mut Context,
+        fractal: &Fractal,
+        to_screen: impl Fn(Complex<f64>) -> (f64, f64),
+        grabbed: Option<usize>
+    ) -> GameResult {
+        let mut mesh = MeshBuilder::new();
+        let mut any = false;
+
+        for (index, root) in fractal.roots().iter().enumerate() {
+            let (x, y) = to_screen(*root);
+            if !(x.abs() < OFF_SCREEN && y.abs() < OFF_SCREEN) {
+                continue;
+            }
+
+            let (r, g, b) = fractal.root_color(index);
+            let outline = if grabbed == Some(index) { 3f32 } else { 1.5f32 };
+
+            mesh.circle(DrawMode::fill(), [x as f32, y as f32], ROOT_RADIUS, 0.5f32, Color::from_rgb(r, g, b))?;
+            mesh.circle(DrawMode::stroke(outline), [x as f32, y as f32], ROOT_RADIUS, 0.5f32, Color::WHITE)?;
+            any = true;
+        }
+
+        if !any {
+            return Ok(()); // building an empty mesh is an error
+        }
+
+        let mesh = mesh.build(ctx)?;
+        graphics::draw(ctx, &mesh, DrawParam::default())
+    }
+
+    /// `orbit` as a line through markers at each iterate, labeled with where it ends up.
+    pub fn draw_orbit(&self, ctx: &mut Context, orbit: &Orbit, to_screen: impl Fn(Complex<f64>) -> (f64, f64)) -> GameResult {
+        let limit = OFF_SCREEN;
+
+        let mut points: Vec<[f32; 2]> = Vec::with_capacity(orbit.points.len());
+        for point in &orbit.points {
+            let (x, y) = to_screen(*point);
+            if !x.is_finite() || !y.is_finite() {
+                break;
+            }
+
+            let point = [x.max(-limit).min(limit) as f32, y.max(-limit).min(limit) as f32];
+            match points.last() {
+                Some(last) if (last[0]-point[0]).abs() < 0.5f32 && (last[1]-point[1]).abs() < 0.5f32 => {},
+                _ => points.push(point),
+            }
+        }
+
+        let mut mesh = MeshBuilder::new();
+        if points.len() > 1 {
+            mesh.polyline(DrawMode::stroke(1.5f32), &points, Color::WHITE)?;
+        }
+        for point in &points {
+            mesh.circle(DrawMode::fill(), *point, 3f32, 0.5f32, Color::WHITE)?;
+        }
+        let mesh = mesh.build(ctx)?;
+        graphics::draw(ctx, &mesh, DrawParam::default())?;
+
+        let label = Text::new(TextFragment::new(outcome(orbit)).font(self.font).scale(PxScale::from(FONT_SIZE)));
+        let last = points.last().copied().unwrap_or([0f32, 0f32]);
+        graphics::draw(ctx, &label, DrawParam::default().dest([last[0] + MARGIN, last[1] + MARGIN]))
+    }
+}
+
+/// Where `orbit` went, in words.
+pub fn outcome(orbit: &Orbit) -> String {
+    let last = orbit.points[orbit.points.len()-1];
+
+    match orbit.outcome {
+        Outcome::Converged { root: Some(root), iters } => format!("root {} ({}) in {} iterations", root, complex(last, 4), iters),
+        Outcome::Converged { root: None, iters } => format!("{} in {} iterations", complex(last, 4), iters),
+        Outcome::Cycle { period } => format!("stuck in a {}-cycle", period),
+        Outcome::Unsettled => format!("unsettled after {} iterations", Fractal::MAX_ITERS),
+    }
+}
+
+/// Enough decimals to tell apart points `width` apart.
+pub fn decimals(width: f64) -> usize {
+    (3f64 - width.log10().floor()).max(2f64) as usize
+}
+
+pub fn complex(c: Complex<f64>, decimals: usize) -> String {
+    format!("{:.*} {} {:.*}i", decimals, c.re, if c.im < 0f64 { '-' } else { '+' }, decimals, c.im.abs())
+}
+
+pub fn view(view: &Rectangle<f64>) -> String {
+    let decimals = decimals(view.right - view.left);
+    format!("re {:.*} to {:.*}, im {:.*} to {:.*}", decimals, view.left, decimals, view.right, decimals, view.top, decimals, view.bottom)
+}
+
+/// Highest power first, e.g. `(1.00 + 0.00i)z^3 + (-1.00 + 0.00i)`.
+pub fn polynomial(coefficients: &[Complex<f64>]) -> String {
+    let terms: Vec<String> = coefficients.iter()
+        .enumerate()
+        .rev()
+        .map(|(power, c)| match power {
+            0 => format!("({})", complex(*c, 2)),
+            1 => format!("({})z", complex(*c, 2)),
+            _ => format!("({})z^{}", complex(*c, 2), power),
+        })
+        .collect();
+
+    terms.join(" + ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn polynomials_read_highest_power_first() {
+        let cubic = [Complex::new(-1f64, 0f64), Complex::new(0f64, 0f64), Complex::new(0f64, 2.5f64), Complex::new(1f64, -0.25f64)];
+
+        assert_eq!(polynomial(&cubic), "(1.00 - 0.25i)z^3 + (0.00 + 2.50i)z^2 + (0.00 + 0.00i)z + (-1.00 + 0.00i)");
+    }
+
+    #[test]
+    fn deeper_zooms_get_more_decimals() {
+        assert_eq!(decimals(10f64), 2);
+        assert_eq!(decimals(1f64), 3);
+        assert_eq!(decimals(0.0004f64), 7);
+    }
+}
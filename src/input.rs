@@ -1,9 +1,358 @@
-pub struct Input {
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+
+use ggez::event::{KeyCode, KeyMods};
+use serde::Deserialize;
+
+/// Everything the keyboard can make the viewer do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+pub enum Action {
+    Quit,
+    Generate,
+    ResetZoom,
+    Save,
+    Export,
+    MarkZoomStart,
+    ExportZoom,
+    CyclePalette,
+    CycleSupersampling,
+    ToggleSubdivision,
+    ToggleVerification,
+    ToggleBackground,
+    ToggleGrid,
+    ToggleHud,
+    ToggleOrbit,
+    PanLeft,
+    PanRight,
+    PanUp,
+    PanDown,
+    ZoomIn,
+    ZoomOut,
+    CycleSelection,
+    Undo,
+    Redo,
+    Bookmark,
+    NextBookmark,
+    NewRandom,
+    PreviousRandom,
+    Help,
+}
 
+impl Action {
+    /// In the order they're listed in the help.
+    pub const ALL: [Action; 29] = [
+        Action::Quit,
+        Action::Generate,
+        Action::ResetZoom,
+        Action::Save,
+        Action::Export,
+        Action::MarkZoomStart,
+        Action::ExportZoom,
+        Action::CyclePalette,
+        Action::CycleSupersampling,
+        Action::ToggleSubdivision,
+        Action::ToggleVerification,
+        Action::ToggleBackground,
+        Action::ToggleGrid,
+        Action::ToggleHud,
+        Action::ToggleOrbit,
+        Action::PanLeft,
+        Action::PanRight,
+        Action::PanUp,
+        Action::PanDown,
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::CycleSelection,
+        Action::Undo,
+        Action::Redo,
+        Action::Bookmark,
+        Action::NextBookmark,
+        Action::NewRandom,
+        Action::PreviousRandom,
+        Action::Help,
+    ];
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Quit => "Quit",
+            Action::Generate => "Generate the fractal with current view",
+            Action::ResetZoom => "Rescale the view back to the last generation",
+            Action::Save => "Save the last generated image to file",
+            Action::Export => "Render the view at poster size into a file, in the background",
+            Action::MarkZoomStart => "Start zoom videos from the current view",
+            Action::ExportZoom => "Render a video zooming from the start view to the current one",
+            Action::CyclePalette => "Switch to the next color palette",
+            Action::CycleSupersampling => "Switch to the next kind of supersampling, or off",
+            Action::ToggleSubdivision => "Switch between brute force and subdivision",
+            Action::ToggleVerification => "Check subdivision against brute force",
+            Action::ToggleBackground => "Save points that don't converge see-through or black",
+            Action::ToggleGrid => "Show or hide the axes and grid, in saved images too",
+            Action::ToggleHud => "Show or hide the overlay",
+            Action::ToggleOrbit => "Follow the cursor with its Newton orbit",
+            Action::PanLeft => "Move the view left",
+            Action::PanRight => "Move the view right",
+            Action::PanUp => "Move the view up",
+            Action::PanDown => "Move the view down",
+            Action::ZoomIn => "Zoom in on the middle",
+            Action::ZoomOut => "Zoom out from the middle",
+            Action::CycleSelection => "Switch between square, window shaped and free selections",
+            Action::Undo => "Go back to the previous view",
+            Action::Redo => "Go forward again after going back",
+            Action::Bookmark => "Bookmark the current view and polynomial",
+            Action::NextBookmark => "Jump to the next bookmark",
+            Action::NewRandom => "Make a new random fractal",
+            Action::PreviousRandom => "Go back to the random fractal before this one",
+            Action::Help => "Print these key bindings",
+        }
+    }
+
+    fn default_chords(self) -> &'static [&'static str] {
+        match self {
+            Action::Quit => &["q"],
+            Action::Generate => &["g"],
+            Action::ResetZoom => &["r"],
+            Action::Save => &["ctrl+s"],
+            Action::Export => &["e"],
+            Action::MarkZoomStart => &["lbracket"],
+            Action::ExportZoom => &["rbracket"],
+            Action::CyclePalette => &["p"],
+            Action::CycleSupersampling => &["k"],
+            Action::ToggleSubdivision => &["m"],
+            Action::ToggleVerification => &["v"],
+            Action::ToggleBackground => &["t"],
+            Action::ToggleGrid => &["x"],
+            Action::ToggleHud => &["h"],
+            Action::ToggleOrbit => &["o"],
+            Action::PanLeft => &["left", "a"],
+            Action::PanRight => &["right", "d"],
+            Action::PanUp => &["up", "w"],
+            Action::PanDown => &["down", "s"],
+            Action::ZoomIn => &["equals", "shift+equals", "numpadadd"],
+            Action::ZoomOut => &["minus", "numpadsubtract"],
+            Action::CycleSelection => &["c"],
+            Action::Undo => &["ctrl+z"],
+            Action::Redo => &["ctrl+y", "ctrl+shift+z"],
+            Action::Bookmark => &["b"],
+            Action::NextBookmark => &["n"],
+            Action::NewRandom => &["f"],
+            Action::PreviousRandom => &["shift+f"],
+            Action::Help => &["f1"],
+        }
+    }
+}
+
+const KEYS: &[(&str, KeyCode)] = &[
+    ("a", KeyCode::A), ("b", KeyCode::B), ("c", KeyCode::C), ("d", KeyCode::D), ("e", KeyCode::E),
+    ("f", KeyCode::F), ("g", KeyCode::G), ("h", KeyCode::H), ("i", KeyCode::I), ("j", KeyCode::J),
+    ("k", KeyCode::K), ("l", KeyCode::L), ("m", KeyCode::M), ("n", KeyCode::N), ("o", KeyCode::O),
+    ("p", KeyCode::P), ("q", KeyCode::Q), ("r", KeyCode::R), ("s", KeyCode::S), ("t", KeyCode::T),
+    ("u", KeyCode::U), ("v", KeyCode::V), ("w", KeyCode::W), ("x", KeyCode::X), ("y", KeyCode::Y),
+    ("z", KeyCode::Z),
+    ("0", KeyCode::Key0), ("1", KeyCode::Key1), ("2", KeyCode::Key2), ("3", KeyCode::Key3), ("4", KeyCode::Key4),
+    ("5", KeyCode::Key5), ("6", KeyCode::Key6), ("7", KeyCode::Key7), ("8", KeyCode::Key8), ("9", KeyCode::Key9),
+    ("f1", KeyCode::F1), ("f2", KeyCode::F2), ("f3", KeyCode::F3), ("f4", KeyCode::F4), ("f5", KeyCode::F5),
+    ("f6", KeyCode::F6), ("f7", KeyCode::F7), ("f8", KeyCode::F8), ("f9", KeyCode::F9), ("f10", KeyCode::F10),
+    ("f11", KeyCode::F11), ("f12", KeyCode::F12),
+    ("up", KeyCode::Up), ("down", KeyCode::Down), ("left", KeyCode::Left), ("right", KeyCode::Right),
+    ("escape", KeyCode::Escape), ("return", KeyCode::Return), ("space", KeyCode::Space), ("tab", KeyCode::Tab),
+    ("backspace", KeyCode::Back), ("insert", KeyCode::Insert), ("delete", KeyCode::Delete),
+    ("home", KeyCode::Home), ("end", KeyCode::End), ("pageup", KeyCode::PageUp), ("pagedown", KeyCode::PageDown),
+    ("minus", KeyCode::Minus), ("equals", KeyCode::Equals), ("plus", KeyCode::Plus),
+    ("comma", KeyCode::Comma), ("period", KeyCode::Period), ("slash", KeyCode::Slash), ("backslash", KeyCode::Backslash),
+    ("semicolon", KeyCode::Semicolon), ("apostrophe", KeyCode::Apostrophe), ("grave", KeyCode::Grave),
+    ("lbracket", KeyCode::LBracket), ("rbracket", KeyCode::RBracket),
+    ("numpadadd", KeyCode::NumpadAdd), ("numpadsubtract", KeyCode::NumpadSubtract),
+];
+
+const MODIFIERS: [(&str, KeyMods); 4] = [
+    ("ctrl", KeyMods::CTRL),
+    ("shift", KeyMods::SHIFT),
+    ("alt", KeyMods::ALT),
+    ("logo", KeyMods::LOGO),
+];
+
+/// A key along with the modifiers that have to be held down with it, written like `ctrl+shift+z`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Chord {
+    pub key: KeyCode,
+    pub mods: KeyMods,
+}
+
+impl Chord {
+    pub fn parse(chord: &str) -> Result<Chord, String> {
+        let chord = chord.to_lowercase();
+        let mut parts: Vec<&str> = chord.split('+').map(str::trim).collect();
+        let key = parts.pop().unwrap_or_default();
+
+        let mut mods = KeyMods::NONE;
+        for part in parts {
+            match MODIFIERS.iter().find(|(name, _)| *name == part) {
+                Some((_, modifier)) => mods |= *modifier,
+                None => return Err(format!("unknown modifier `{}`", part)),
+            }
+        }
+
+        match KEYS.iter().find(|(name, _)| *name == key) {
+            Some((_, key)) => Ok(Chord { key: *key, mods }),
+            None => Err(format!("unknown key `{}`", key)),
+        }
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, modifier) in &MODIFIERS {
+            if self.mods.contains(*modifier) {
+                write!(f, "{}+", name)?;
+            }
+        }
+
+        let (name, _) = KEYS.iter().find(|(_, key)| *key == self.key).expect("named key");
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Chords {
+    One(String),
+    Many(Vec<String>),
+}
+
+/// Maps key presses to actions.
+pub struct Input {
+    bindings: Vec<(Chord, Action)>,
 }
 
 impl Input {
-    pub fn new() {
+    pub fn new() -> Input {
+        let bindings = Action::ALL.iter()
+            .flat_map(|action| action.default_chords().iter().map(move |chord| (Chord::parse(chord).expect("default chord"), *action)))
+            .collect();
+
+        Input { bindings }
+    }
+
+    /// The default bindings, with any actions listed in the TOML file at `path` rebound, e.g.
+    ///
+    /// ```toml
+    /// Save = "f2"
+    /// Generate = ["g", "return"]
+    /// ```
+    pub fn load(path: &str) -> Input {
+        let mut input = Input::new();
+
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(_) => return input, // nothing to override
+        };
+
+        let overrides: HashMap<Action, Chords> = match toml::from_str(&contents) {
+            Ok(overrides) => overrides,
+            Err(e) => {
+                eprintln!("{}: {}, using the default key bindings", path, e);
+                return input;
+            },
+        };
+
+        for (action, chords) in overrides {
+            let chords = match chords {
+                Chords::One(chord) => vec![chord],
+                Chords::Many(chords) => chords,
+            };
+
+            input.bindings.retain(|(_, a)| *a != action);
+            for chord in chords {
+                match Chord::parse(&chord) {
+                    Ok(chord) => input.bind(chord, action),
+                    Err(e) => eprintln!("{}: {} for {:?}", path, e, action),
+                }
+            }
+        }
+
+        input
+    }
+
+    /// Binds `chord` to `action`, taking it away from whatever it was bound to before.
+    pub fn bind(&mut self, chord: Chord, action: Action) {
+        self.bindings.retain(|(c, _)| *c != chord);
+        self.bindings.push((chord, action));
+    }
+
+    pub fn action(&self, key: KeyCode, mods: KeyMods) -> Option<Action> {
+        self.bindings.iter()
+            .find(|(chord, _)| chord.key == key && chord.mods == mods)
+            .map(|(_, action)| *action)
+    }
+
+    pub fn chords(&self, action: Action) -> Vec<Chord> {
+        self.bindings.iter()
+            .filter(|(_, a)| *a == action)
+            .map(|(chord, _)| *chord)
+            .collect()
+    }
+
+    /// A markdown table of every action and what it's bound to.
+    pub fn help(&self) -> String {
+        let rows: Vec<(String, &str)> = Action::ALL.iter()
+            .map(|action| {
+                let chords: Vec<String> = self.chords(*action).iter().map(|chord| format!("`{}`", chord)).collect();
+                (chords.join(", "), action.description())
+            })
+            .collect();
+
+        let key_width = rows.iter().map(|(chords, _)| chords.len()).max().unwrap_or(0).max(3);
+        let description_width = rows.iter().map(|(_, description)| description.len()).max().unwrap_or(0);
+
+        let mut help = format!("| {:key_width$} | {:description_width$} |\n", "Key", "Function");
+        help += &format!("|{}|{}|\n", "-".repeat(key_width + 2), "-".repeat(description_width + 2));
+        for (chords, description) in rows {
+            help += &format!("| {:key_width$} | {:description_width$} |\n", chords, description);
+        }
+
+        help
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chords_round_trip() {
+        for chord in ["q", "ctrl+s", "ctrl+shift+z", "f1", "alt+left"] {
+            assert_eq!(Chord::parse(chord).unwrap().to_string(), chord);
+        }
+
+        assert_eq!(Chord::parse("Shift + Ctrl + Z").unwrap().to_string(), "ctrl+shift+z");
+        assert!(Chord::parse("hyper+q").is_err());
+        assert!(Chord::parse("ctrl+").is_err());
+    }
+
+    #[test]
+    fn modifiers_have_to_match() {
+        let mut input = Input::new();
+        input.bind(Chord::parse("ctrl+s").unwrap(), Action::Quit);
+
+        assert_eq!(input.action(KeyCode::S, KeyMods::NONE), Some(Action::PanDown));
+        assert_eq!(input.action(KeyCode::S, KeyMods::CTRL), Some(Action::Quit));
+        assert_eq!(input.action(KeyCode::S, KeyMods::CTRL | KeyMods::SHIFT), None);
+    }
+
+    #[test]
+    fn every_action_has_a_default_binding() {
+        let input = Input::new();
+        for action in &Action::ALL {
+            assert!(!input.chords(*action).is_empty(), "{:?} is unbound", action);
+        }
+    }
+
+    #[test]
+    fn readme_lists_the_default_bindings() {
+        let readme = fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/README.md")).expect("README.md");
+        let help = Input::new().help();
 
+        assert!(readme.contains(&help), "README.md key table is out of date, it should be:\n{}", help);
     }
 }
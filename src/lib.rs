@@ -0,0 +1,25 @@
+//! Newton's fractals without a window: the polynomials, Newton's method on them, which root each
+//! pixel goes to, coloring, and writing it all out as PNGs, posters and animations. The viewer in
+//! `main.rs` is built on top of this, behind the `viewer` feature.
+//!
+//! ```no_run
+//! use newtonian_rustsody::fractals::{Background, Fractal, Rectangle, SaveOptions};
+//! use newtonian_rustsody::random::Random;
+//!
+//! let view = Rectangle { left: -5f64, top: -5f64, right: 5f64, bottom: 5f64 };
+//! let mut fractal = Fractal::with_seed((800, 800), view, &Random::default(), 1234);
+//! fractal.generate();
+//! fractal.save_to_path("1234.png", SaveOptions { background: Background::Opaque, grid: false }).expect("saved image");
+//! ```
+
+pub mod animation;
+pub mod export;
+pub mod fractals;
+pub mod grid;
+pub mod polynomial;
+pub mod random;
+pub mod render;
+pub mod scene;
+pub mod simd;
+#[cfg(test)]
+mod testing;
@@ -1,19 +1,53 @@
-use std::time::{SystemTime};
-
-use gfx_core::format::{Format, SurfaceType, ChannelType};
+use std::sync::mpsc::Receiver;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
 
 use ggez::{Context, ContextBuilder, GameResult};
-use ggez::conf::{NumSamples, WindowSetup, WindowMode};
+use ggez::conf::{WindowSetup, WindowMode};
 use ggez::event::{self, EventHandler, KeyCode, KeyMods, MouseButton};
-use ggez::graphics::{self, DrawParam, Rect, Mesh, DrawMode, Canvas, Color};
+use ggez::graphics::{self, BlendMode, Drawable, DrawParam, Rect, Mesh, DrawMode, Color};
 use ggez::timer;
 
-mod fractals;
-use fractals::Fractal;
-use fractals::Rectangle;
+use newtonian_rustsody::{animation, export, polynomial, render};
+use newtonian_rustsody::fractals::{Background, Fractal, Pattern, Sampling, SaveOptions, Supersampling, Timings};
+use newtonian_rustsody::animation::{Easing, Video, Zoom};
+use newtonian_rustsody::export::ExportEvent;
+use newtonian_rustsody::fractals::Rectangle;
+use newtonian_rustsody::grid::Grid;
+use newtonian_rustsody::random::{Distribution, Random};
+use newtonian_rustsody::render::{Pass, Renderer, RenderEvent};
+use newtonian_rustsody::scene::Scene;
+
+mod history;
+mod hud;
+mod input;
+mod texture;
+use history::{Bookmark, Bookmarks, History};
+use hud::Hud;
+use input::{Action, Input};
+use texture::FractalImage;
 
-#[allow(unused_imports)]
 use num::Complex;
+use rand::Rng;
+
+/// Whatever it's been told on the command line.
+struct Options {
+    generate_immediately: bool,
+    poster_size: (usize, usize),
+    video: Video,
+    /// What to start from, a random one if it's not given
+    seed: Option<u64>,
+    random: Random,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SelectionShape {
+    Square,
+    /// The same shape as the window, so nothing gets stretched
+    Window,
+    /// Whatever shape it's dragged out to
+    Free,
+}
 
 struct App {
     width: u32,
@@ -22,20 +56,105 @@ struct App {
     mouse: (f64, f64),
     scroll_scale: f64,
 
+    /// Whether anything's changed since the window was last drawn.
     draw: bool,
+    last_draw: Instant,
     generate: bool,
-
+    verify: bool,
+    background: Background,
+    /// Shown over the fractal, and put into saved images too.
+    grid: bool,
+
+    input: Input,
+    hud: Hud,
+
+    /// How much of the plane a pixel covered to begin with, to measure the zoom against.
+    initial_scale: f64,
+    history: History,
+    bookmarks: Bookmarks,
+    /// How new random fractals get made.
+    random: Random,
+    /// Every random fractal's seed so far, and which of them is up.
+    seeds: Vec<u64>,
+    seed_position: usize,
     fractal: Fractal,
+    image: FractalImage,
+    renderer: Renderer,
+
+    job: Option<u64>,
+    progress: Option<(f64, Duration)>,
+    last_render: Option<(Duration, Timings)>,
+    title: String,
+
+    /// How big exports come out, whatever the window's size.
+    poster_size: (usize, usize),
+    video: Video,
+    /// Where zoom videos zoom in from.
+    zoom_start: Rectangle<f64>,
+    /// The poster or video being exported, and what it is.
+    export: Option<(String, Receiver<ExportEvent>)>,
+    export_progress: Option<f64>,
+
+    orbit_follows_cursor: bool,
+    pinned_orbit: Option<Complex<f64>>,
+
+    /// Every root of the polynomial, and which of them is being dragged around.
+    dragging: Option<(Vec<Complex<f64>>, usize)>,
 
     selecting: bool,
+    selection_shape: SelectionShape,
     selection: Rectangle<f64>,
     selection_center: (f64, f64),
 
+    /// Where the mouse was last, while it's dragging the view around.
+    panning: Option<(f64, f64)>,
+
+    /// The part of the current image the window is showing, in its pixels.
     zoom_view: Rectangle<f64>,
+    /// When the zoom last changed, if it hasn't been generated since.
+    zoomed_at: Option<Instant>,
 }
 
 impl App {
-    pub fn new(ctx: &mut Context, width: u32, height: u32, scroll_scale: f64, generate_immediately: bool) -> App {
+    /// How long the zoom has to stay put before it's generated.
+    const SETTLE: Duration = Duration::from_millis(400);
+    /// How often the window gets drawn even if nothing's changed, in case it's been covered up.
+    const REDRAW: Duration = Duration::from_secs(1);
+    /// Selections smaller than this across, in pixels, are taken as clicks.
+    const SMALLEST_SELECTION: f64 = 4f64;
+    /// How much of the window the keys pan by.
+    const PAN_STEP: f64 = 0.1f64;
+    /// How much the keys zoom in by.
+    const ZOOM_STEP: f64 = 1.25f64;
+    /// What switching supersampling goes through, roughly from cheapest to best.
+    const SUPERSAMPLINGS: [Option<Supersampling>; 6] = [
+        None,
+        Some(Supersampling { pattern: Pattern::RotatedGrid, size: 2, adaptive: true }),
+        Some(Supersampling { pattern: Pattern::RotatedGrid, size: 3, adaptive: true }),
+        Some(Supersampling { pattern: Pattern::Grid, size: 2, adaptive: false }),
+        Some(Supersampling { pattern: Pattern::Grid, size: 3, adaptive: false }),
+        Some(Supersampling { pattern: Pattern::Jittered, size: 3, adaptive: false }),
+    ];
+
+    /// [-5, 5] across the shorter side of a `width`x`height` window, and how much of the plane
+    /// each pixel covers in it.
+    fn initial_view(width: u32, height: u32) -> (Rectangle<f64>, f64) {
+        let scale = 10f64/(width.min(height) as f64);
+        let (half_width, half_height) = (scale*(width as f64)/2f64, scale*(height as f64)/2f64);
+
+        (Rectangle { left: -half_width, top: -half_height, right: half_width, bottom: half_height }, scale)
+    }
+
+    pub fn new(ctx: &mut Context, width: u32, height: u32, scroll_scale: f64, options: Options) -> App {
+        let (initial_view, initial_scale) = Self::initial_view(width, height);
+
+        let seed = options.seed.unwrap_or_else(|| rand::thread_rng().gen());
+
+        let mut fractal = Fractal::with_coefficients((width as usize, height as usize), initial_view, Vec::new());
+        if !fractal.set_seed(&options.random, seed) {
+            eprintln!("seed {}: couldn't get every root in the view, keeping the last try", seed);
+        }
+
         App {
             width,
             height,
@@ -44,14 +163,28 @@ impl App {
             scroll_scale,
 
             draw: true,
-            generate: generate_immediately,
-
-            fractal: Fractal::with_random_coefficients(
-                ctx,
-                (width as usize, height as usize),
-                Rectangle { left: -5f64, top: -5f64, right: 5f64, bottom: 5f64 },
-                4
-            ),
+            last_draw: Instant::now(),
+            generate: options.generate_immediately,
+            verify: false,
+            background: Background::Opaque,
+            grid: false,
+
+            input: Input::load("keys.toml"),
+            hud: Hud::new(ctx),
+
+            initial_scale,
+            history: History::new(initial_view),
+            bookmarks: Bookmarks::load("bookmarks.toml"),
+            random: options.random,
+            seeds: vec![seed],
+            seed_position: 0,
+            fractal,
+            image: {
+                // the fractal's opaque, but anything drawn over it blends in
+                let mut image = FractalImage::new();
+                image.set_blend_mode(Some(BlendMode::Replace));
+                image
+            },
             /*
             Fractal::with_coefficients(
                 (width as usize, height as usize),
@@ -65,43 +198,448 @@ impl App {
                 ]
             )
             */
+            renderer: Renderer::new(),
+
+            job: None,
+            progress: None,
+            last_render: None,
+            title: String::new(),
+            poster_size: options.poster_size,
+            video: options.video,
+            zoom_start: initial_view,
+            export: None,
+            export_progress: None,
+
+            orbit_follows_cursor: false,
+            pinned_orbit: None,
+
+            dragging: None,
 
             selecting: false,
+            selection_shape: SelectionShape::Window,
             selection: Rectangle { left: 0f64, top: 0f64, right: 0f64, bottom: 0f64 },
             selection_center: (0f64, 0f64),
 
+            panning: None,
+
             zoom_view: Rectangle { left: 0f64, top: 0f64, right: width as f64, bottom: height as f64 },
+            zoomed_at: None,
+        }
+    }
+
+    fn status(&self) -> String {
+        if let (Some((what, _)), Some(progress)) = (&self.export, self.export_progress) {
+            format!("Newtonian Rustsody - exporting {} {:.0}%", what, progress*100f64)
+        } else if let Some((progress, elapsed)) = self.progress {
+            format!("Newtonian Rustsody - rendering {:.0}% ({:.2}s)", progress*100f64, elapsed.as_secs_f64())
+        } else if let Some((elapsed, timings)) = self.last_render {
+            format!(
+                "Newtonian Rustsody - rendered in {:.2}s (roots {:.2}s, texture {:.2}s)",
+                elapsed.as_secs_f64(),
+                timings.roots.as_secs_f64(),
+                timings.texture.as_secs_f64()
+            )
+        } else {
+            String::from("Newtonian Rustsody")
+        }
+    }
+
+    /// The pixel of the current image that's under `(x, y)` in the window.
+    fn to_image(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        (
+            self.zoom_view.left + x*(self.zoom_view.right - self.zoom_view.left)/(self.width as f64),
+            self.zoom_view.top + y*(self.zoom_view.bottom - self.zoom_view.top)/(self.height as f64)
+        )
+    }
+
+    /// Where in the window `point` is shown.
+    fn to_screen(&self, point: Complex<f64>) -> (f64, f64) {
+        let (x, y) = self.fractal.pixel_at(point);
+        (
+            (x - self.zoom_view.left)*(self.width as f64)/(self.zoom_view.right - self.zoom_view.left),
+            (y - self.zoom_view.top)*(self.height as f64)/(self.zoom_view.bottom - self.zoom_view.top)
+        )
+    }
+
+    /// The point under `(x, y)` in the window.
+    fn point_at(&self, position: (f64, f64)) -> Complex<f64> {
+        self.fractal.point_at(self.to_image(position))
+    }
+
+    /// What the window is showing, which is ahead of the fractal's view while a zoom is pending.
+    fn shown_view(&self) -> Rectangle<f64> {
+        let top_left = self.point_at((0f64, 0f64));
+        let bottom_right = self.point_at((self.width as f64, self.height as f64));
+
+        Rectangle { left: top_left.re, top: top_left.im, right: bottom_right.re, bottom: bottom_right.im }
+    }
+
+    /// How far in from the initial view the window is.
+    fn zoom(&self) -> f64 {
+        let view = self.shown_view();
+        self.initial_scale*(self.width as f64)/(view.right - view.left)
+    }
+
+    fn hud_lines(&self) -> Vec<String> {
+        let view = &self.shown_view();
+        let zoom = self.zoom();
+
+        let render = if let Some((progress, elapsed)) = self.progress {
+            format!("rendering {:.0}% ({:.2}s)", progress*100f64, elapsed.as_secs_f64())
+        } else if let Some((elapsed, _)) = self.last_render {
+            format!("rendered in {:.2}s", elapsed.as_secs_f64())
+        } else {
+            String::from("not rendered yet")
+        };
+
+        let mut lines = vec![
+            format!("cursor: {}", hud::complex(self.point_at(self.mouse), hud::decimals(view.right - view.left))),
+            format!("view: {}", hud::view(view)),
+            format!("zoom: {:.2}x", zoom),
+            format!("roots: {}", self.fractal.roots().len()),
+            format!("supersampling: {}", self.fractal.supersampling().map_or(String::from("off"), |s| s.to_string())),
+            format!("history: {}/{}", self.history.position().0, self.history.position().1),
+            format!("seed: {}", self.fractal.seed().map_or(String::from("none, it's been changed"), |seed| seed.to_string())),
+            format!("p(z) = {}", hud::polynomial(self.fractal.coefficients())),
+            render,
+        ];
+
+        if let (Some((what, _)), Some(progress)) = (&self.export, self.export_progress) {
+            lines.push(format!("exporting {} {:.0}%", what, progress*100f64));
         }
+
+        lines
+    }
+
+    /// Zooms in on `rect`, in window pixels, so it'll fill the window once it's generated.
+    fn zoom_into(&mut self, rect: Rectangle<f64>) {
+        let (left, top) = self.to_image((rect.left, rect.top));
+        let (right, bottom) = self.to_image((rect.right, rect.bottom));
+
+        self.renderer.cancel_all();
+        self.zoomed_at = Some(Instant::now());
+        self.zoom_view = Rectangle { left, top, right, bottom };
+    }
+
+    /// Zooms by `factor` keeping `(x, y)` in the window where it is, in for factors under 1.
+    fn zoom_around(&mut self, (x, y): (f64, f64), factor: f64) {
+        let (width, height) = (self.width as f64, self.height as f64);
+
+        self.zoom_into(Rectangle {
+            left: x - x*factor,
+            top: y - y*factor,
+            right: x + (width - x)*factor,
+            bottom: y + (height - y)*factor,
+        });
+    }
+
+    /// Moves what's shown by `(dx, dy)` window pixels, as if it had been dragged.
+    fn pan(&mut self, (dx, dy): (f64, f64)) {
+        self.zoom_into(Rectangle {
+            left: -dx,
+            top: -dy,
+            right: self.width as f64 - dx,
+            bottom: self.height as f64 - dy,
+        });
+    }
+
+    /// Cancels whatever's rendering to render `target` instead.
+    fn render(&mut self, target: Fractal, passes: &[Pass]) {
+        self.renderer.cancel_all();
+        self.job = Some(self.renderer.submit(target, passes));
+    }
+
+    /// Renders `target` and moves on to its view in the history.
+    fn go_to(&mut self, target: Fractal) {
+        let view = self.show(target);
+        self.history.visit(view);
+    }
+
+    /// Renders a view from the history, which stays where it is in there.
+    fn go_back_to(&mut self, view: Rectangle<f64>) {
+        let mut target = self.fractal.clone();
+        target.set_view(view);
+
+        let view = self.show(target);
+        self.history.replace(view);
+    }
+
+    /// Renders `target`, showing the current image zoomed in on its view until then. Its view
+    /// gets widened to the window's shape, in case it was recorded at another one, and that's
+    /// the view it returns.
+    fn show(&mut self, mut target: Fractal) -> Rectangle<f64> {
+        let size = (self.width as usize, self.height as usize);
+        let view = export::fit(target.view(), size);
+        target.set_size(size);
+        target.set_view(view);
+
+        let (left, top) = self.fractal.pixel_at(Complex::new(view.left, view.top));
+        let (right, bottom) = self.fractal.pixel_at(Complex::new(view.right, view.bottom));
+
+        self.zoom_view = Rectangle { left, top, right, bottom };
+        self.render(target, &render::PROGRESSIVE);
+
+        view
+    }
+
+    /// What saved images get besides the fractal, going by what's on screen.
+    fn save_options(&self) -> SaveOptions {
+        SaveOptions { background: self.background, grid: self.grid }
+    }
+
+    /// Starts `export` on a copy of the fractal, into `destination`, unless another export is
+    /// still going. `what` is what the overlay calls it.
+    fn start_export(&mut self, what: String, destination: String, export: impl FnOnce(Fractal, String, SaveOptions) -> Receiver<ExportEvent>) {
+        if self.export.is_some() {
+            println!("already exporting");
+            return;
+        }
+
+        println!("exporting {} to {}", what, destination);
+        self.export = Some((what, export(self.fractal.clone(), destination, self.save_options())));
+        self.export_progress = Some(0f64);
+    }
+
+    /// Renders the random fractal from the seed `seed_position` is on, from the top.
+    fn go_to_seed(&mut self) {
+        let seed = self.seeds[self.seed_position];
+        let (view, _) = Self::initial_view(self.width, self.height);
+
+        let mut target = self.fractal.clone();
+        target.set_view(view);
+        if !target.set_seed(&self.random, seed) {
+            eprintln!("seed {}: couldn't get every root in the view, keeping the last try", seed);
+        }
+        self.go_to(target);
+    }
+
+    /// The closest root marker within grabbing distance of `(x, y)`.
+    fn root_under(&self, (x, y): (f64, f64)) -> Option<usize> {
+        let distance = |root: &Complex<f64>| {
+            let (root_x, root_y) = self.to_screen(*root);
+            (root_x - x).hypot(root_y - y)
+        };
+
+        self.fractal.roots().iter()
+            .enumerate()
+            .map(|(index, root)| (index, distance(root)))
+            .filter(|(_, distance)| *distance <= hud::ROOT_RADIUS as f64)
+            .min_by(|a, b| a.1.partial_cmp(&b.1).expect("finite distance"))
+            .map(|(index, _)| index)
+    }
+
+    /// Renders the polynomial with the dragged root wherever it is now.
+    fn render_dragged(&mut self, passes: &[Pass]) {
+        if let Some((roots, _)) = &self.dragging {
+            let coefficients = self.fractal.coefficients();
+            let leading = coefficients[coefficients.len()-1];
+
+            let mut target = self.fractal.clone();
+            target.set_coefficients(polynomial::from_roots(leading, roots));
+            self.render(target, passes);
+        }
+    }
+
+    /// Re-expresses the pending zoom in the pixels of `frame`, a freshly rendered frame.
+    fn rebase_zoom_view(&mut self, frame: &Fractal) {
+        let (left, top) = frame.pixel_at(self.fractal.point_at((self.zoom_view.left, self.zoom_view.top)));
+        let (right, bottom) = frame.pixel_at(self.fractal.point_at((self.zoom_view.right, self.zoom_view.bottom)));
+
+        self.zoom_view = Rectangle { left, top, right, bottom };
     }
 }
 
 impl EventHandler for App {
-    fn key_down_event(&mut self, ctx: &mut Context, keycode: KeyCode, _keymods: KeyMods, _repeat: bool) {
-        match keycode {
-            KeyCode::Q => {
+    fn key_down_event(&mut self, ctx: &mut Context, keycode: KeyCode, keymods: KeyMods, _repeat: bool) {
+        self.draw = true;
+
+        let action = match self.input.action(keycode, keymods) {
+            Some(action) => action,
+            None => return,
+        };
+
+        match action {
+            Action::Quit => {
                 event::quit(ctx);
             },
-            KeyCode::G => {
+            Action::Generate => {
                 self.generate = true;
             },
-            KeyCode::R => {
-                self.zoom_view = Rectangle { left: 0f64, top: 0f64, right: self.width as f64, bottom: self.height as f64 };
+            Action::ResetZoom => {
+                let (width, height) = self.fractal.size();
+                self.zoom_view = Rectangle { left: 0f64, top: 0f64, right: width as f64, bottom: height as f64 };
+                self.zoomed_at = None;
+            },
+            Action::Save => {
+                if let Err(e) = self.fractal.save_to_file(&format!("{}.png", timestamped("out")), self.save_options()) {
+                    eprintln!("save failed, {}", e);
+                }
             },
-            KeyCode::S => {
-                let timestamp = match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
-                    Ok(ts) => ts.as_nanos(),
-                    _ => 0
+            Action::Export => {
+                let size = self.poster_size;
+                let what = format!("{}x{} poster", size.0, size.1);
+                self.start_export(what, format!("{}.png", timestamped("out/poster")), |fractal, path, options| {
+                    export::spawn(fractal, path, size, options)
+                });
+            },
+            Action::MarkZoomStart => {
+                self.zoom_start = self.shown_view();
+                println!("zoom videos start from {}", hud::view(&self.zoom_start));
+            },
+            Action::ExportZoom => {
+                let video = self.video;
+                let zoom = Zoom { from: self.zoom_start, to: self.shown_view(), frames: video.frames, easing: video.easing };
+                self.start_export(format!("{} frame zoom", video.frames), timestamped("out/zoom"), |fractal, directory, options| {
+                    animation::spawn(fractal, zoom, video, directory, options)
+                });
+            },
+            Action::CyclePalette => {
+                self.fractal.cycle_palette();
+                println!("palette: {}", self.fractal.palette_name());
+            },
+            Action::CycleSupersampling => {
+                let current = Self::SUPERSAMPLINGS.iter().position(|s| *s == self.fractal.supersampling()).unwrap_or(0);
+                let supersampling = Self::SUPERSAMPLINGS[(current + 1) % Self::SUPERSAMPLINGS.len()];
+                println!("supersampling: {}", supersampling.map_or(String::from("off"), |s| s.to_string()));
+
+                self.fractal.set_supersampling(supersampling);
+                self.generate = true;
+            },
+            Action::ToggleSubdivision => {
+                let sampling = match self.fractal.sampling() {
+                    Sampling::BruteForce => Sampling::Subdivision,
+                    Sampling::Subdivision => Sampling::BruteForce,
+                };
+                println!("sampling: {:?}", sampling);
+
+                self.fractal.set_sampling(sampling);
+                self.generate = true;
+            },
+            Action::ToggleVerification => {
+                self.verify = !self.verify;
+                println!("verifying subdivision: {}", self.verify);
+            },
+            Action::ToggleBackground => {
+                self.background = match self.background {
+                    Background::Opaque => Background::Transparent,
+                    Background::Transparent => Background::Opaque,
+                };
+                println!("saved background: {:?}", self.background);
+            },
+            Action::ToggleGrid => {
+                self.grid = !self.grid;
+            },
+            Action::ToggleHud => {
+                self.hud.visible = !self.hud.visible;
+            },
+            Action::ToggleOrbit => {
+                self.orbit_follows_cursor = !self.orbit_follows_cursor;
+                self.pinned_orbit = None;
+            },
+            Action::Undo => {
+                if let Some(view) = self.history.undo() {
+                    self.go_back_to(view);
+                }
+            },
+            Action::Redo => {
+                if let Some(view) = self.history.redo() {
+                    self.go_back_to(view);
+                }
+            },
+            Action::Bookmark => {
+                let view = *self.fractal.view();
+                let center = Complex::new((view.left + view.right)/2f64, (view.top + view.bottom)/2f64);
+                let zoom = self.zoom();
+
+                let name = format!("{:.2}x at {}", zoom, hud::complex(center, hud::decimals(view.right - view.left)));
+                println!("bookmarked {}", name);
+
+                self.bookmarks.add(Bookmark::new(name, self.fractal.coefficients(), *self.fractal.view()));
+            },
+            Action::NewRandom => {
+                self.seeds.push(rand::thread_rng().gen());
+                self.seed_position = self.seeds.len() - 1;
+                self.go_to_seed();
+            },
+            Action::PreviousRandom => {
+                if self.seed_position == 0 {
+                    println!("no random fractals before this one");
+                    return;
+                }
+
+                self.seed_position -= 1;
+                self.go_to_seed();
+            },
+            Action::NextBookmark => {
+                let bookmark = match self.bookmarks.next() {
+                    Some(bookmark) => bookmark.clone(),
+                    None => {
+                        println!("no bookmarks yet");
+                        return;
+                    },
                 };
-                self.fractal.save_to_file(format!("out_{}.png", timestamp).as_str());
+                println!("going to {}", bookmark.name);
+
+                let mut target = self.fractal.clone();
+                target.set_coefficients(bookmark.coefficients());
+                target.set_view(bookmark.view);
+                self.go_to(target);
+            },
+            Action::PanLeft => {
+                self.pan((Self::PAN_STEP*(self.width as f64), 0f64));
+            },
+            Action::PanRight => {
+                self.pan((-Self::PAN_STEP*(self.width as f64), 0f64));
+            },
+            Action::PanUp => {
+                self.pan((0f64, Self::PAN_STEP*(self.height as f64)));
+            },
+            Action::PanDown => {
+                self.pan((0f64, -Self::PAN_STEP*(self.height as f64)));
+            },
+            Action::ZoomIn => {
+                self.zoom_around(((self.width/2) as f64, (self.height/2) as f64), 1f64/Self::ZOOM_STEP);
+            },
+            Action::ZoomOut => {
+                self.zoom_around(((self.width/2) as f64, (self.height/2) as f64), Self::ZOOM_STEP);
+            },
+            Action::CycleSelection => {
+                self.selection_shape = match self.selection_shape {
+                    SelectionShape::Square => SelectionShape::Window,
+                    SelectionShape::Window => SelectionShape::Free,
+                    SelectionShape::Free => SelectionShape::Square,
+                };
+                println!("selection: {:?}", self.selection_shape);
+            },
+            Action::Help => {
+                print!("{}", self.input.help());
             },
-            _ => {}
         }
     }
 
     fn mouse_button_down_event(&mut self, _ctx: &mut Context, button: MouseButton, x: f32, y: f32) {
+        self.draw = true;
+
+        if button == MouseButton::Right {
+            self.pinned_orbit = Some(self.point_at((x as f64, y as f64)));
+        }
+
+        if button == MouseButton::Middle {
+            self.panning = Some((x as f64, y as f64));
+        }
+
         if button == MouseButton::Left {
             let (x, y) = (x as f64, y as f64);
 
+            if let Some(grabbed) = self.root_under((x, y)).map(|index| self.fractal.roots()[index]) {
+                // roots that never matched up can shift the rest along, so go by where it is
+                let roots = polynomial::match_order(self.fractal.roots(), polynomial::roots(self.fractal.coefficients()));
+                let closest = (0..roots.len())
+                    .min_by(|a, b| (roots[*a] - grabbed).norm_sqr().total_cmp(&(roots[*b] - grabbed).norm_sqr()));
+                self.dragging = closest.map(|index| (roots, index));
+                return;
+            }
+
             self.selecting = true;
             self.selection_center = (x, y);
             self.selection = Rectangle { left: x, top: y, right: x, bottom: y };
@@ -109,93 +647,223 @@ impl EventHandler for App {
     }
 
     fn mouse_button_up_event(&mut self, _ctx: &mut Context, button: MouseButton, _x: f32, _y: f32) {
+        self.draw = true;
+
+        if button == MouseButton::Middle {
+            self.panning = None;
+        }
+
+        if button == MouseButton::Left && self.dragging.is_some() {
+            self.render_dragged(&render::PROGRESSIVE);
+            self.dragging = None;
+            return;
+        }
+
         if button == MouseButton::Left {
+            if !self.selecting {
+                return;
+            }
             self.selecting = false;
-            self.zoom_view = Rectangle {
-                left: self.selection.left * (self.zoom_view.right - self.zoom_view.left)/(self.width as f64) + self.zoom_view.left,
-                top: self.selection.top * (self.zoom_view.bottom - self.zoom_view.top)/(self.height as f64) + self.zoom_view.top,
-                right: self.selection.right * (self.zoom_view.right - self.zoom_view.left)/(self.width as f64) + self.zoom_view.left,
-                bottom: self.selection.bottom * (self.zoom_view.bottom - self.zoom_view.top)/(self.height as f64) + self.zoom_view.top
-            };
+
+            let too_small = (self.selection.right - self.selection.left).min(self.selection.bottom - self.selection.top) < Self::SMALLEST_SELECTION;
+            if too_small {
+                return; // just a click
+            }
+
+            self.zoom_into(self.selection);
         }
     }
 
     fn mouse_motion_event(&mut self, _ctx: &mut Context, x: f32, y: f32, _dx: f32, _dy: f32) {
+        self.draw = true;
+
         self.mouse = (x as f64, y as f64);
 
-        let offset = (self.mouse.0-self.selection_center.0).abs().max((self.mouse.1-self.selection_center.1).abs());
+        let point = self.point_at(self.mouse);
+        if let Some((roots, index)) = &mut self.dragging {
+            roots[*index] = point;
+            self.render_dragged(&render::PREVIEW);
+            return;
+        }
+
+        if let Some(last) = self.panning {
+            self.pan((self.mouse.0 - last.0, self.mouse.1 - last.1));
+            self.panning = Some(self.mouse);
+        }
+
+        let (dx, dy) = ((self.mouse.0-self.selection_center.0).abs(), (self.mouse.1-self.selection_center.1).abs());
+        let aspect = (self.width as f64)/(self.height as f64);
+        let (offset_x, offset_y) = match self.selection_shape {
+            SelectionShape::Square => (dx.max(dy), dx.max(dy)),
+            SelectionShape::Window => (dx.max(dy*aspect), dx.max(dy*aspect)/aspect),
+            SelectionShape::Free => (dx, dy),
+        };
+
         self.selection = Rectangle {
-            left: self.selection_center.0-offset,
-            top: self.selection_center.1-offset,
-            right: self.selection_center.0+offset,
-            bottom: self.selection_center.1+offset
+            left: self.selection_center.0-offset_x,
+            top: self.selection_center.1-offset_y,
+            right: self.selection_center.0+offset_x,
+            bottom: self.selection_center.1+offset_y
         };
     }
 
     fn mouse_wheel_event(&mut self, _ctx: &mut Context, _x: f32, y: f32) {
-        let s = y as f64;
-        let (x, y) = (
-            (self.mouse.0 as f64)*(self.zoom_view.right-self.zoom_view.left)/(self.width as f64) + self.zoom_view.left,
-            (self.mouse.1 as f64)*(self.zoom_view.bottom-self.zoom_view.top)/(self.height as f64) + self.zoom_view.top
-        );
+        self.draw = true;
 
         self.selecting = false;
-        let scale = self.scroll_scale*s;
+        self.zoom_around(self.mouse, 1f64 - self.scroll_scale*(y as f64));
+    }
+
+    fn resize_event(&mut self, ctx: &mut Context, width: f32, height: f32) {
+        if width < 1f32 || height < 1f32 {
+            return; // minimized
+        }
+
+        self.draw = true;
+
+        graphics::set_screen_coordinates(ctx, Rect::new(0f32, 0f32, width, height)).expect("screen coordinates");
+
+        // the same part of the plane stays in the middle, at the same size per pixel
+        let (old_width, old_height) = (self.width as f64, self.height as f64);
+        let (center_x, center_y) = self.to_image((old_width/2f64, old_height/2f64));
+        let scale_x = (self.zoom_view.right - self.zoom_view.left)/old_width;
+        let scale_y = (self.zoom_view.bottom - self.zoom_view.top)/old_height;
+
+        self.width = width as u32;
+        self.height = height as u32;
+
+        let (half_width, half_height) = (scale_x*(self.width as f64)/2f64, scale_y*(self.height as f64)/2f64);
         self.zoom_view = Rectangle {
-            left: scale*x + (1f64-scale)*self.zoom_view.left,
-            top: scale*y + (1f64-scale)*self.zoom_view.top,
-            right: scale*x + (1f64-scale)*self.zoom_view.right,
-            bottom: scale*y + (1f64-scale)*self.zoom_view.bottom
+            left: center_x - half_width,
+            top: center_y - half_height,
+            right: center_x + half_width,
+            bottom: center_y + half_height,
         };
+
+        self.renderer.cancel_all();
+        self.zoomed_at = Some(Instant::now());
     }
 
-    fn update(&mut self, _ctx: &mut Context) -> GameResult {
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        if let Some(zoomed_at) = self.zoomed_at {
+            if zoomed_at.elapsed() >= Self::SETTLE && !self.selecting && self.panning.is_none() {
+                self.generate = true;
+            }
+        }
+
         if self.generate {
             self.generate = false;
+            self.zoomed_at = None;
+
+            let mut target = self.fractal.clone();
+            target.set_view(self.shown_view());
 
-            self.fractal.scale_view(Rectangle {
-                left: self.zoom_view.left/(self.width as f64),
-                top: self.zoom_view.top/(self.height as f64),
-                right: self.zoom_view.right/(self.width as f64),
-                bottom: self.zoom_view.bottom/(self.height as f64)
-            });
+            self.go_to(target);
+        }
 
-            // reset the zoom
-            self.zoom_view = Rectangle { left: 0f64, top: 0f64, right: self.width as f64, bottom: self.height as f64 };
+        for event in self.renderer.poll() {
+            self.draw = true;
 
-            self.fractal.generate();
+            if Some(event.job()) != self.job {
+                continue; // left over from a cancelled job
+            }
+
+            match event {
+                RenderEvent::Started { .. } => {
+                    self.progress = Some((0f64, Duration::from_secs(0)));
+                },
+                RenderEvent::Progress { progress, elapsed, .. } => {
+                    self.progress = Some((progress, elapsed));
+                },
+                RenderEvent::Frame { fractal, timings, .. } => {
+                    // the last image stays up until the first frame of the new one comes in
+                    self.rebase_zoom_view(&fractal);
+                    self.fractal = *fractal;
+                    self.last_render = Some((Duration::from_secs(0), timings));
+                },
+                RenderEvent::Finished { elapsed, .. } => {
+                    self.job = None;
+                    self.progress = None;
+                    if let Some((_, timings)) = self.last_render {
+                        self.last_render = Some((elapsed, timings));
+                    }
+
+                    if self.verify && self.fractal.sampling() == Sampling::Subdivision {
+                        let fractal = self.fractal.clone();
+                        thread::spawn(move || {
+                            let mismatched = fractal.verify_subdivision();
+                            println!(
+                                "subdivision: {} pixels mismatched{}",
+                                mismatched.len(),
+                                if mismatched.is_empty() { String::new() } else { format!(", e.g. {:?}", &mismatched[..mismatched.len().min(10)]) }
+                            );
+                        });
+                    }
+                },
+                RenderEvent::Cancelled { .. } => {
+                    self.job = None;
+                    self.progress = None;
+                },
+            }
+        }
 
+        let events: Vec<ExportEvent> = self.export.as_ref().map_or(Vec::new(), |(_, export)| export.try_iter().collect());
+        for event in events {
             self.draw = true;
+
+            match event {
+                ExportEvent::Progress(progress) => self.export_progress = Some(progress),
+                ExportEvent::Finished { path, elapsed } => {
+                    println!("exported {} in {:.2}s", path, elapsed.as_secs_f64());
+                    self.export = None;
+                    self.export_progress = None;
+                },
+                ExportEvent::Failed(e) => {
+                    eprintln!("export failed, {}", e);
+                    self.export = None;
+                    self.export_progress = None;
+                },
+            }
+        }
+
+        let status = self.status();
+        if status != self.title {
+            graphics::set_window_title(ctx, &status);
+            self.title = status;
         }
 
         Ok(())
     }
 
     fn draw(&mut self, ctx: &mut Context) -> GameResult {
-        graphics::clear(ctx, graphics::Color { r: 0f32, g: 0f32, b: 0f32, a: 1f32});
-
-        let fractal_canvas = Canvas::new(
-            ctx,
-            self.width as u16,
-            self.height as u16,
-            NumSamples::One,
-            Format(
-                SurfaceType::R8_G8_B8_A8,
-                ChannelType::Uint,
-            )
-        ).expect("fractal canvas");
+        if !self.draw && self.last_draw.elapsed() < Self::REDRAW {
+            // nothing to show that isn't up already, so there's no point keeping the CPU busy
+            timer::sleep(Duration::from_millis(10));
+            return Ok(());
+        }
+        self.draw = false;
+        self.last_draw = Instant::now();
 
-        graphics::set_canvas(ctx, Some(&fractal_canvas));
+        graphics::clear(ctx, graphics::Color { r: 0f32, g: 0f32, b: 0f32, a: 1f32});
 
-        /*
-        -(self.zoom_view.left*(self.width as f64)/(self.zoom_view.right-self.zoom_view.left)) as i32,
-        -(self.zoom_view.top*(self.height as f64)/(self.zoom_view.bottom-self.zoom_view.top)) as i32,
-        ((self.width*self.width) as f64/(self.zoom_view.right-self.zoom_view.left)) as u32,
-        ((self.height*self.height) as f64/(self.zoom_view.bottom-self.zoom_view.top)) as u32
-        */
+        // stretched so the pending zoom fills the window, until it's been generated
+        let scale = [
+            self.width as f32/(self.zoom_view.right - self.zoom_view.left) as f32,
+            self.height as f32/(self.zoom_view.bottom - self.zoom_view.top) as f32,
+        ];
+        let transform = DrawParam::default()
+            .dest([-self.zoom_view.left as f32*scale[0], -self.zoom_view.top as f32*scale[1]])
+            .scale(scale);
+        self.image.update(ctx, &self.fractal).expect("fractal image");
+        graphics::draw(ctx, &self.image, transform).expect("drawn fractal");
+
+        if self.grid {
+            let grid = Grid::new(&self.shown_view(), (self.width as usize, self.height as usize));
+            self.hud.draw_grid(ctx, &grid).expect("drawn grid");
+        }
 
-        graphics::draw(ctx, &self.fractal, DrawParam::default()).expect("drawn fractal");
-        graphics::set_canvas(ctx, None);
+        let grabbed = self.dragging.as_ref().map(|(_, index)| *index);
+        self.hud.draw_roots(ctx, &self.fractal, |point| self.to_screen(point), grabbed).expect("drawn roots");
 
         if self.selecting {
             let bounds = Rect::new(
@@ -209,6 +877,14 @@ impl EventHandler for App {
             graphics::draw(ctx, &selection_rect, DrawParam::default()).expect("drawn selection rectangle");
         }
 
+        let orbit_start = if self.orbit_follows_cursor { Some(self.point_at(self.mouse)) } else { self.pinned_orbit };
+        if let Some(start) = orbit_start {
+            self.hud.draw_orbit(ctx, &self.fractal.orbit(start), |point| self.to_screen(point)).expect("drawn orbit");
+        }
+
+        let lines = self.hud_lines();
+        self.hud.draw(ctx, &lines).expect("drawn HUD");
+
         timer::yield_now();
         graphics::present(ctx)
     }
@@ -219,17 +895,55 @@ fn main() {
     let width = 800;
     let height = 800;
 
-    let generate_immediately = if let Some(_val) = std::env::args().nth(1) {
-        true // TODO: should probably do flag analysis more properly
-    } else {
-        false
+    let mut options = Options {
+        generate_immediately: false,
+        poster_size: (8192, 8192),
+        video: Video { frames: 120, easing: Easing::EaseInOut, gif: false, fps: 30 },
+        seed: None,
+        random: Random::default(),
     };
+    let mut scene = None;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--poster" => {
+                let size = args.next().expect("--poster WIDTHxHEIGHT");
+                let mut sides = size.split('x').map(|side| side.parse::<usize>().expect("poster side in pixels"));
+                options.poster_size = (sides.next().expect("poster width"), sides.next().expect("poster height"));
+            },
+            "--frames" => options.video.frames = args.next().and_then(|frames| frames.parse().ok()).expect("--frames COUNT"),
+            "--easing" => {
+                let easing = args.next().expect("--easing linear|ease-in|ease-out|ease-in-out");
+                options.video.easing = Easing::parse(&easing).expect("--easing linear|ease-in|ease-out|ease-in-out");
+            },
+            "--fps" => options.video.fps = args.next().and_then(|fps| fps.parse().ok()).expect("--fps FRAMES_PER_SECOND"),
+            "--gif" => options.video.gif = true,
+            "--degree" => options.random.degree = args.next().and_then(|degree| degree.parse().ok()).expect("--degree DEGREE"),
+            "--distribution" => {
+                let distribution = args.next().expect("--distribution NAME[:PARAMETER]");
+                options.random.distribution = Distribution::parse(&distribution)
+                    .expect("--distribution box|disc|gaussian|lattice|unit|real|roots-of-unity, e.g. disc:2");
+            },
+            "--monic" => options.random.monic = true,
+            "--roots-in-view" => options.random.roots_in_view = true,
+            "--seed" => options.seed = Some(args.next().and_then(|seed| seed.parse().ok()).expect("--seed NUMBER")),
+            "--scene" => scene = Some(args.next().expect("--scene SCENE.toml")),
+            _ => options.generate_immediately = true, // TODO: should probably do flag analysis more properly
+        }
+    }
+
+    if let Some(path) = scene {
+        render_scene(&path);
+        return;
+    }
 
     let window_setup = WindowSetup::default()
         .title("Newtonian Rustsody");
 
     let window_mode = WindowMode::default()
         .dimensions(width as f32, height as f32)
+        .resizable(true)
         .borderless(false);
 
     let (mut ctx, event_loop) = ContextBuilder::new("newtonian_rustsody", "kshpin")
@@ -237,6 +951,41 @@ fn main() {
         .window_mode(window_mode)
         .build()
         .expect("context and event loop");
-    let app = App::new(&mut ctx, width, height, scroll_scale, generate_immediately);
+    let app = App::new(&mut ctx, width, height, scroll_scale, options);
     event::run(ctx, event_loop, app);
 }
+
+/// `name` followed by the time now, so nothing saved overwrites anything else.
+fn timestamped(name: &str) -> String {
+    let timestamp = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map_or(0, |ts| ts.as_nanos());
+    format!("{}_{}", name, timestamp)
+}
+
+/// Renders the scene in the file at `path` into `out/`, without opening a window.
+fn render_scene(path: &str) {
+    let scene = match Scene::load(path) {
+        Ok(scene) => scene,
+        Err(e) => {
+            eprintln!("{}: {}", path, e);
+            return;
+        },
+    };
+
+    let name = std::path::Path::new(path).file_stem().map_or(String::from("scene"), |stem| stem.to_string_lossy().into_owned());
+    let directory = format!("out/{}", name);
+    let options = SaveOptions { background: Background::Opaque, grid: false };
+
+    let beginning = Instant::now();
+    let mut reported = 0f64;
+    let rendered = scene.render(&directory, options, |done| {
+        if done - reported >= 0.1f64 || done == 1f64 {
+            reported = done;
+            println!("{}: {:.0}%", directory, done*100f64);
+        }
+    });
+
+    match rendered {
+        Ok(()) => println!("rendered {} frames into {} in {:.2}s", scene.frames, directory, beginning.elapsed().as_secs_f64()),
+        Err(e) => eprintln!("{}: {}", directory, e),
+    }
+}
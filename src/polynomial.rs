@@ -0,0 +1,146 @@
+use num::complex::Complex;
+
+/// Coefficients, lowest power first, of `leading*(z - roots[0])*(z - roots[1])*...`.
+pub fn from_roots(leading: Complex<f64>, roots: &[Complex<f64>]) -> Vec<Complex<f64>> {
+    let mut coefficients = vec![leading];
+
+    for root in roots {
+        // multiplying by (z - root) shifts everything up a power and subtracts root times the old
+        let mut next = vec![Complex::new(0f64, 0f64); coefficients.len() + 1];
+        for (power, c) in coefficients.iter().enumerate() {
+            next[power + 1] += c;
+            next[power] -= c*root;
+        }
+        coefficients = next;
+    }
+
+    coefficients
+}
+
+/// Every root of the polynomial with `coefficients`, lowest power first, found all at once
+/// with the Durand-Kerner method. Zeros at the top don't count towards the degree.
+pub fn roots(coefficients: &[Complex<f64>]) -> Vec<Complex<f64>> {
+    const MAX_ITERS: usize = 500;
+    const TOLERANCE: f64 = 1e-14;
+
+    let degree = coefficients.iter().rposition(|c| c.norm_sqr() > 0f64).unwrap_or(0);
+    if degree == 0 {
+        return Vec::new();
+    }
+
+    let leading = coefficients[degree];
+    let monic: Vec<Complex<f64>> = coefficients[..=degree].iter().map(|c| c/leading).collect();
+
+    // every root is inside this radius, and the starting guesses can't be symmetric
+    let radius = 1f64 + monic[..degree].iter().map(|c| c.norm()).fold(0f64, f64::max);
+    let seed = Complex::new(0.4f64, 0.9f64);
+    let mut roots: Vec<Complex<f64>> = (0..degree).map(|i| seed.powu(i as u32)*radius/seed.norm().powi(i as i32)).collect();
+
+    let evaluate = |z: Complex<f64>| monic.iter().rev().fold(Complex::new(0f64, 0f64), |result, c| result*z + c);
+
+    for _ in 0..MAX_ITERS {
+        let mut change = 0f64;
+
+        for i in 0..degree {
+            let denominator = (0..degree)
+                .filter(|j| *j != i)
+                .fold(Complex::new(1f64, 0f64), |product, j| product*(roots[i] - roots[j]));
+
+            let step = evaluate(roots[i])/denominator;
+            roots[i] -= step;
+            change = change.max(step.norm());
+        }
+
+        if change < TOLERANCE*radius {
+            break;
+        }
+    }
+
+    roots
+}
+
+/// `roots` reordered so each one is as close as it can be to the one in the same place in
+/// `previous`, going greedily from the closest pair. Any extra roots go at the end, and if there
+/// are fewer than before, the ones after an unmatched place move up into it.
+pub fn match_order(previous: &[Complex<f64>], mut roots: Vec<Complex<f64>>) -> Vec<Complex<f64>> {
+    let mut pairs: Vec<(f64, usize, usize)> = previous.iter()
+        .enumerate()
+        .flat_map(|(i, p)| roots.iter().enumerate().map(move |(j, r)| ((p - r).norm_sqr(), i, j)))
+        .collect();
+    pairs.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut matched: Vec<Option<Complex<f64>>> = vec![None; previous.len()];
+    let mut taken = vec![false; roots.len()];
+    for (_, i, j) in pairs {
+        if matched[i].is_none() && !taken[j] {
+            matched[i] = Some(roots[j]);
+            taken[j] = true;
+        }
+    }
+
+    let mut ordered: Vec<Complex<f64>> = matched.into_iter().flatten().collect();
+    let mut j = 0;
+    roots.retain(|_| {
+        j += 1;
+        !taken[j-1]
+    });
+    ordered.extend(roots);
+
+    ordered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: &[Complex<f64>], b: &[Complex<f64>]) {
+        assert_eq!(a.len(), b.len());
+        for (a, b) in a.iter().zip(b) {
+            assert!((a - b).norm() < 1e-9, "{} != {}", a, b);
+        }
+    }
+
+    #[test]
+    fn expanding_roots_gives_the_coefficients() {
+        let roots = [Complex::new(1f64, 0f64), Complex::new(-1f64, 0f64), Complex::new(0f64, 2f64)];
+
+        // 2(z^2 - 1)(z - 2i) = 2z^3 - 4iz^2 - 2z + 4i
+        assert_close(&from_roots(Complex::new(2f64, 0f64), &roots), &[
+            Complex::new(0f64, 4f64),
+            Complex::new(-2f64, 0f64),
+            Complex::new(0f64, -4f64),
+            Complex::new(2f64, 0f64),
+        ]);
+    }
+
+    #[test]
+    fn roots_round_trip() {
+        let expected = vec![
+            Complex::new(-2.5f64, 1f64),
+            Complex::new(0.3f64, -0.7f64),
+            Complex::new(4f64, 4f64),
+            Complex::new(0.31f64, -0.69f64),
+            Complex::new(-1f64, -3f64),
+        ];
+
+        let found = roots(&from_roots(Complex::new(-3f64, 1.5f64), &expected));
+        assert_close(&match_order(&expected, found), &expected);
+    }
+
+    #[test]
+    fn zeros_at_the_top_lower_the_degree() {
+        let expected = vec![Complex::new(1f64, 2f64), Complex::new(-3f64, 0f64)];
+        let mut coefficients = from_roots(Complex::new(2f64, 0f64), &expected);
+        coefficients.push(Complex::new(0f64, 0f64));
+
+        assert_close(&match_order(&expected, roots(&coefficients)), &expected);
+    }
+
+    #[test]
+    fn matching_keeps_roots_in_place() {
+        let previous = [Complex::new(0f64, 0f64), Complex::new(5f64, 0f64)];
+        let moved = vec![Complex::new(9f64, 9f64), Complex::new(5.5f64, 0f64), Complex::new(0.5f64, 0f64)];
+
+        assert_eq!(match_order(&previous, moved), vec![Complex::new(0.5f64, 0f64), Complex::new(5.5f64, 0f64), Complex::new(9f64, 9f64)]);
+    }
+}
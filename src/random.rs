@@ -0,0 +1,278 @@
+use std::f64::consts::PI;
+use std::fmt;
+
+use num::complex::Complex;
+use rand::{Rng, SeedableRng};
+use rand::distributions::Normal;
+use rand_pcg::Pcg64Mcg;
+use serde::Deserialize;
+
+use crate::fractals::Rectangle;
+use crate::polynomial;
+
+/// Where random coefficients come from.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Distribution {
+    /// Evenly over [-size, size] + [-size, size]i
+    Box { size: f64 },
+    /// Evenly over the disc
+    Disc { radius: f64 },
+    /// Normally around 0 in both parts
+    Gaussian { sigma: f64 },
+    /// Whole numbers in [-size, size] for both parts
+    Lattice { size: i64 },
+    /// Anywhere on the unit circle
+    Unit,
+    /// Evenly over [-size, size], nothing imaginary
+    Real { size: f64 },
+    /// Not coefficients at all, but roots: the roots of unity, each knocked up to `jitter` away
+    RootsOfUnity { jitter: f64 },
+}
+
+impl Distribution {
+    /// `name`, or `name:parameter` to set its size, radius, sigma or jitter, which has to be more than 0.
+    pub fn parse(description: &str) -> Result<Distribution, String> {
+        let mut parts = description.splitn(2, ':');
+        let name = parts.next().unwrap_or("");
+        let parameter = match parts.next() {
+            Some(parameter) => Some(parameter.parse::<f64>().map_err(|_| format!("{}: {} isn't a number", name, parameter))?),
+            None => None,
+        };
+        let parameter = |default: f64| parameter.unwrap_or(default);
+
+        let distribution = match name {
+            "box" => Distribution::Box { size: parameter(10f64) },
+            "disc" => Distribution::Disc { radius: parameter(10f64) },
+            "gaussian" => Distribution::Gaussian { sigma: parameter(5f64) },
+            "lattice" => Distribution::Lattice { size: parameter(5f64) as i64 },
+            "unit" => Distribution::Unit,
+            "real" => Distribution::Real { size: parameter(10f64) },
+            "roots-of-unity" => Distribution::RootsOfUnity { jitter: parameter(0.1f64) },
+            _ => return Err(format!("there's no {} distribution", name)),
+        };
+
+        distribution.check()?;
+        Ok(distribution)
+    }
+
+    /// Whether it can be drawn from at all, which it can't with a parameter of 0 or less.
+    pub fn check(&self) -> Result<(), String> {
+        let parameter = match *self {
+            Distribution::Box { size } | Distribution::Real { size } => size,
+            Distribution::Disc { radius } => radius,
+            Distribution::Gaussian { sigma } => sigma,
+            Distribution::Lattice { size } => size as f64,
+            Distribution::RootsOfUnity { jitter } => jitter,
+            Distribution::Unit => return Ok(()),
+        };
+
+        if parameter > 0f64 && parameter.is_finite() {
+            Ok(())
+        } else {
+            Err(format!("{} needs its parameter to be more than 0", self))
+        }
+    }
+
+    fn sample(&self, rng: &mut Pcg64Mcg) -> Complex<f64> {
+        match *self {
+            Distribution::Box { size } => Complex::new(rng.gen_range(-size, size), rng.gen_range(-size, size)),
+            Distribution::Disc { radius } | Distribution::RootsOfUnity { jitter: radius } => {
+                // the square root spreads them evenly instead of bunching them up in the middle
+                Complex::from_polar(radius*rng.gen::<f64>().sqrt(), 2f64*PI*rng.gen::<f64>())
+            },
+            Distribution::Gaussian { sigma } => {
+                let normal = Normal::new(0f64, sigma);
+                Complex::new(rng.sample(normal), rng.sample(normal))
+            },
+            Distribution::Lattice { size } => Complex::new(rng.gen_range(-size, size + 1) as f64, rng.gen_range(-size, size + 1) as f64),
+            Distribution::Unit => Complex::from_polar(1f64, 2f64*PI*rng.gen::<f64>()),
+            Distribution::Real { size } => Complex::new(rng.gen_range(-size, size), 0f64),
+        }
+    }
+}
+
+impl fmt::Display for Distribution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Distribution::Box { size } => write!(f, "box:{}", size),
+            Distribution::Disc { radius } => write!(f, "disc:{}", radius),
+            Distribution::Gaussian { sigma } => write!(f, "gaussian:{}", sigma),
+            Distribution::Lattice { size } => write!(f, "lattice:{}", size),
+            Distribution::Unit => write!(f, "unit"),
+            Distribution::Real { size } => write!(f, "real:{}", size),
+            Distribution::RootsOfUnity { jitter } => write!(f, "roots-of-unity:{}", jitter),
+        }
+    }
+}
+
+/// How random polynomials get made.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Random {
+    pub degree: u32,
+    pub distribution: Distribution,
+    /// A leading coefficient of 1
+    pub monic: bool,
+    /// Drawing again until every root is inside the view
+    pub roots_in_view: bool,
+}
+
+impl Default for Random {
+    fn default() -> Random {
+        Random {
+            degree: 4,
+            distribution: Distribution::Box { size: 10f64 },
+            monic: false,
+            roots_in_view: false,
+        }
+    }
+}
+
+impl Random {
+    /// How many times to draw before giving up on getting every root in the view.
+    const TRIES: usize = 1000;
+
+    /// Coefficients, lowest power first, always the same for the same `seed` (and `view`, if
+    /// the roots have to be in it), and whether they are. If no draw gets every root in the
+    /// view in `TRIES`, it's the last one with `false`.
+    pub fn coefficients(&self, seed: u64, view: &Rectangle<f64>) -> (Vec<Complex<f64>>, bool) {
+        // a PRNG that's pinned down by name, unlike `StdRng`, so saved seeds keep giving the same polynomial
+        let mut rng = Pcg64Mcg::seed_from_u64(seed);
+
+        let (left, right) = (view.left.min(view.right), view.left.max(view.right));
+        let (top, bottom) = (view.top.min(view.bottom), view.top.max(view.bottom));
+        let in_view = |root: &Complex<f64>| left <= root.re && root.re <= right && top <= root.im && root.im <= bottom;
+
+        let mut coefficients = self.draw(&mut rng);
+        for _ in 1..Self::TRIES {
+            if !self.roots_in_view || polynomial::roots(&coefficients).iter().all(in_view) {
+                return (coefficients, true);
+            }
+
+            coefficients = self.draw(&mut rng);
+        }
+
+        (coefficients, false)
+    }
+
+    fn draw(&self, rng: &mut Pcg64Mcg) -> Vec<Complex<f64>> {
+        if let Distribution::RootsOfUnity { .. } = self.distribution {
+            let roots: Vec<Complex<f64>> = (0..self.degree)
+                .map(|k| Complex::from_polar(1f64, 2f64*PI*(k as f64)/(self.degree as f64)) + self.distribution.sample(rng))
+                .collect();
+            let leading = if self.monic { Complex::new(1f64, 0f64) } else { Distribution::Unit.sample(rng) };
+
+            return polynomial::from_roots(leading, &roots);
+        }
+
+        let mut coefficients: Vec<Complex<f64>> = (0..self.degree+1).map(|_| self.distribution.sample(rng)).collect();
+
+        let degree = self.degree as usize;
+        if self.monic {
+            coefficients[degree] = Complex::new(1f64, 0f64);
+        }
+        // a zero at the top would make it a lower degree than asked for, which the lattice can do
+        for _ in 0..Self::TRIES {
+            if coefficients[degree] != Complex::new(0f64, 0f64) {
+                break;
+            }
+            coefficients[degree] = self.distribution.sample(rng);
+        }
+        if coefficients[degree] == Complex::new(0f64, 0f64) {
+            coefficients[degree] = Complex::new(1f64, 0f64);
+        }
+
+        coefficients
+    }
+}
+
+impl fmt::Display for Random {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "degree {}, {}", self.degree, self.distribution)?;
+        if self.monic {
+            write!(f, ", monic")?;
+        }
+        if self.roots_in_view {
+            write!(f, ", roots in view")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view() -> Rectangle<f64> {
+        Rectangle { left: -5f64, top: -5f64, right: 5f64, bottom: 5f64 }
+    }
+
+    fn draws(distribution: Distribution) -> Vec<Complex<f64>> {
+        let random = Random { degree: 6, distribution, ..Random::default() };
+        (0..20).flat_map(|seed| random.coefficients(seed, &view()).0).collect()
+    }
+
+    #[test]
+    fn distributions_stay_in_their_bounds() {
+        assert!(draws(Distribution::Box { size: 2f64 }).iter().all(|c| c.re.abs() <= 2f64 && c.im.abs() <= 2f64));
+        assert!(draws(Distribution::Disc { radius: 3f64 }).iter().all(|c| c.norm() <= 3f64));
+        assert!(draws(Distribution::Lattice { size: 2 }).iter().all(|c| c.re.fract() == 0f64 && c.im.fract() == 0f64 && c.re.abs() <= 2f64));
+        assert!(draws(Distribution::Unit).iter().all(|c| (c.norm() - 1f64).abs() < 1e-12));
+        assert!(draws(Distribution::Real { size: 10f64 }).iter().all(|c| c.im == 0f64));
+
+        let gaussian = draws(Distribution::Gaussian { sigma: 1f64 });
+        let spread = gaussian.iter().map(|c| c.norm_sqr()).sum::<f64>()/(gaussian.len() as f64);
+        assert!(1f64 < spread && spread < 3f64, "{}", spread);
+    }
+
+    #[test]
+    fn constraints_hold() {
+        let monic = Random { degree: 5, distribution: Distribution::Lattice { size: 3 }, monic: true, roots_in_view: false };
+        assert_eq!(monic.coefficients(7, &view()).0.len(), 6);
+        assert_eq!(monic.coefficients(7, &view()).0[5], Complex::new(1f64, 0f64));
+
+        let small = Rectangle { left: -1.5f64, top: -1.5f64, right: 1.5f64, bottom: 1.5f64 };
+        let contained = Random { degree: 4, distribution: Distribution::Disc { radius: 1f64 }, monic: false, roots_in_view: true };
+        for seed in 0..10 {
+            let (coefficients, fits) = contained.coefficients(seed, &small);
+            let roots = polynomial::roots(&coefficients);
+            assert!(fits, "seed {}", seed);
+            assert!(roots.iter().all(|root| root.re.abs() <= 1.5f64 && root.im.abs() <= 1.5f64), "seed {}: {:?}", seed, roots);
+        }
+
+        let unity = Random { degree: 5, distribution: Distribution::RootsOfUnity { jitter: 0.1f64 }, monic: true, roots_in_view: false };
+        let roots = polynomial::roots(&unity.coefficients(3, &view()).0);
+        assert_eq!(roots.len(), 5);
+        assert!(roots.iter().all(|root| (root.norm() - 1f64).abs() <= 0.1f64 + 1e-9));
+
+        // roots two apart never fit in a view that's one across
+        let tiny = Rectangle { left: -0.5f64, top: -0.5f64, right: 0.5f64, bottom: 0.5f64 };
+        let spread = Random { degree: 2, distribution: Distribution::RootsOfUnity { jitter: 0.01f64 }, monic: true, roots_in_view: true };
+        assert!(!spread.coefficients(1, &tiny).1);
+    }
+
+    #[test]
+    fn distributions_parse_with_or_without_a_parameter() {
+        assert_eq!(Distribution::parse("disc"), Ok(Distribution::Disc { radius: 10f64 }));
+        assert_eq!(Distribution::parse("gaussian:2.5"), Ok(Distribution::Gaussian { sigma: 2.5f64 }));
+        assert_eq!(Distribution::parse("roots-of-unity:0.3"), Ok(Distribution::RootsOfUnity { jitter: 0.3f64 }));
+        assert!(Distribution::parse("disc:wide").is_err());
+        assert!(Distribution::parse("triangle").is_err());
+
+        let distribution = Distribution::Lattice { size: 4 };
+        assert_eq!(Distribution::parse(&distribution.to_string()), Ok(distribution));
+    }
+
+    #[test]
+    fn parameters_have_to_be_more_than_zero() {
+        for description in ["box:0", "real:0", "lattice:0", "lattice:0.5", "gaussian:-1", "disc:-2", "roots-of-unity:0"] {
+            assert!(Distribution::parse(description).is_err(), "{}", description);
+        }
+
+        // and if one gets through anyway, e.g. from a scene file, there's still always a leading coefficient
+        let flat = Random { degree: 3, distribution: Distribution::Lattice { size: 0 }, monic: false, roots_in_view: false };
+        assert_eq!(flat.coefficients(1, &view()).0[3], Complex::new(1f64, 0f64));
+    }
+}
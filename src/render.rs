@@ -0,0 +1,233 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::fractals::{Fractal, Timings};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Precision {
+    Single,
+    Double,
+}
+
+/// One refinement step of a render: every `scale`th pixel is generated in `precision`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pass {
+    pub scale: usize,
+    pub precision: Precision,
+}
+
+/// 1/16 of the pixels, then 1/4, then all of them, only the last one in double precision.
+pub const PROGRESSIVE: [Pass; 3] = [
+    Pass { scale: 4, precision: Precision::Single },
+    Pass { scale: 2, precision: Precision::Single },
+    Pass { scale: 1, precision: Precision::Double },
+];
+
+/// Just the roughest pass, for following along with something as it changes.
+pub const PREVIEW: [Pass; 1] = [
+    Pass { scale: 4, precision: Precision::Single },
+];
+
+struct Job {
+    id: u64,
+    fractal: Fractal,
+    passes: Vec<Pass>,
+}
+
+/// What the render worker reports back, in order, for every job it picks up.
+pub enum RenderEvent {
+    Started { job: u64 },
+    /// Overall progress through all of the job's passes, from 0 to 1.
+    Progress { job: u64, progress: f64, elapsed: Duration },
+    /// A pass is done, `fractal` is what it looks like now.
+    Frame { job: u64, fractal: Box<Fractal>, timings: Timings },
+    Finished { job: u64, elapsed: Duration },
+    Cancelled { job: u64 },
+}
+
+impl RenderEvent {
+    pub fn job(&self) -> u64 {
+        match self {
+            RenderEvent::Started { job }
+            | RenderEvent::Progress { job, .. }
+            | RenderEvent::Frame { job, .. }
+            | RenderEvent::Finished { job, .. }
+            | RenderEvent::Cancelled { job } => *job,
+        }
+    }
+}
+
+/// A queue of render jobs worked through one at a time on a background thread.
+pub struct Renderer {
+    jobs: Sender<Job>,
+    events: Receiver<RenderEvent>,
+
+    last_job: u64,
+    /// Every job with an id up to this one has been cancelled.
+    cancelled: Arc<AtomicU64>,
+}
+
+impl Default for Renderer {
+    fn default() -> Renderer {
+        Renderer::new()
+    }
+}
+
+impl Renderer {
+    pub fn new() -> Renderer {
+        let (jobs, job_receiver) = mpsc::channel::<Job>();
+        let (event_sender, events) = mpsc::channel();
+        let cancelled = Arc::new(AtomicU64::new(0));
+
+        let worker_cancelled = Arc::clone(&cancelled);
+        thread::spawn(move || {
+            for job in job_receiver {
+                if Self::work(job, &worker_cancelled, &event_sender).is_err() {
+                    return; // nobody's listening anymore
+                }
+            }
+        });
+
+        Renderer {
+            jobs,
+            events,
+            last_job: 0,
+            cancelled,
+        }
+    }
+
+    fn work(mut job: Job, cancelled: &AtomicU64, events: &Sender<RenderEvent>) -> Result<(), mpsc::SendError<RenderEvent>> {
+        let id = job.id;
+        let beginning = Instant::now();
+        let is_cancelled = || id <= cancelled.load(Ordering::Relaxed);
+
+        if is_cancelled() {
+            return events.send(RenderEvent::Cancelled { job: id });
+        }
+
+        events.send(RenderEvent::Started { job: id })?;
+
+        let passes = job.passes.len() as f64;
+        for (i, pass) in job.passes.iter().enumerate() {
+            let mut reported = 0f64;
+            let keep_going = |done: f64| {
+                let progress = (i as f64 + done)/passes;
+
+                // a percent at a time is plenty
+                if progress - reported >= 0.01 {
+                    reported = progress;
+                    let _ = events.send(RenderEvent::Progress { job: id, progress, elapsed: beginning.elapsed() });
+                }
+
+                !is_cancelled()
+            };
+
+            let timings = match pass.precision {
+                Precision::Single => job.fractal.generate_scaled::<f32>(pass.scale, keep_going),
+                Precision::Double => job.fractal.generate_scaled::<f64>(pass.scale, keep_going),
+            };
+
+            match timings {
+                Some(timings) => events.send(RenderEvent::Frame { job: id, fractal: Box::new(job.fractal.clone()), timings })?,
+                None => return events.send(RenderEvent::Cancelled { job: id }),
+            }
+        }
+
+        events.send(RenderEvent::Finished { job: id, elapsed: beginning.elapsed() })
+    }
+
+    /// Queues up `fractal` to be rendered in `passes`, returning the id of the job.
+    pub fn submit(&mut self, fractal: Fractal, passes: &[Pass]) -> u64 {
+        self.last_job += 1;
+
+        self.jobs.send(Job {
+            id: self.last_job,
+            fractal,
+            passes: passes.to_vec(),
+        }).expect("render worker");
+
+        self.last_job
+    }
+
+    /// Cancels every job submitted so far, whether it's running or still queued.
+    pub fn cancel_all(&mut self) {
+        self.cancelled.store(self.last_job, Ordering::Relaxed);
+    }
+
+    /// Everything the worker reported since the last poll.
+    pub fn poll(&mut self) -> Vec<RenderEvent> {
+        self.events.try_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::fractals::Rectangle;
+    use crate::testing::unity;
+
+    fn cubic(size: usize) -> Fractal {
+        Fractal::with_coefficients(
+            (size, size),
+            Rectangle { left: -2f64, top: -2f64, right: 2f64, bottom: 2f64 },
+            unity(3)
+        )
+    }
+
+    fn wait_for(renderer: &mut Renderer, job: u64) -> Vec<RenderEvent> {
+        let mut events = Vec::new();
+        loop {
+            let event = renderer.events.recv().expect("render worker");
+            let done = matches!(event, RenderEvent::Finished { .. } | RenderEvent::Cancelled { .. });
+
+            if event.job() == job {
+                events.push(event);
+                if done {
+                    return events;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn jobs_report_frames_then_finish() {
+        let mut renderer = Renderer::new();
+        let job = renderer.submit(cubic(64), &PROGRESSIVE);
+
+        let events = wait_for(&mut renderer, job);
+
+        assert!(matches!(events.first(), Some(RenderEvent::Started { .. })));
+        assert!(matches!(events.last(), Some(RenderEvent::Finished { .. })));
+
+        let frames: Vec<&Fractal> = events.iter()
+            .filter_map(|event| match event {
+                RenderEvent::Frame { fractal, .. } => Some(fractal.as_ref()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(frames.len(), PROGRESSIVE.len());
+
+        // the last pass has to come out just like going through all of them by hand
+        let mut expected = cubic(64);
+        expected.generate_scaled::<f32>(4, |_| true);
+        expected.generate_scaled::<f32>(2, |_| true);
+        expected.generate_scaled::<f64>(1, |_| true);
+        assert!(frames.last().unwrap().pixels() == expected.pixels());
+    }
+
+    #[test]
+    fn cancelling_stops_running_and_queued_jobs() {
+        let mut renderer = Renderer::new();
+        // big enough to still be running when it gets cancelled
+        let running = renderer.submit(cubic(2000), &PROGRESSIVE);
+        let queued = renderer.submit(cubic(64), &PROGRESSIVE);
+        renderer.cancel_all();
+
+        assert!(matches!(wait_for(&mut renderer, running).last(), Some(RenderEvent::Cancelled { .. })));
+        assert!(matches!(wait_for(&mut renderer, queued).as_slice(), [RenderEvent::Cancelled { .. }]));
+    }
+}
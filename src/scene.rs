@@ -0,0 +1,352 @@
+use std::fs;
+
+use num::complex::Complex;
+use serde::Deserialize;
+
+use crate::animation::{Easing, Frames, Video};
+use crate::fractals::{Fractal, Rectangle, SaveOptions};
+use crate::polynomial;
+use crate::random::Random;
+
+fn complex((re, im): (f64, f64)) -> Complex<f64> {
+    Complex::new(re, im)
+}
+
+/// Where a root or coefficient is over the course of the animation, with points as `[re, im]`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "path", rename_all = "lowercase")]
+pub enum Path {
+    Fixed { at: (f64, f64) },
+    Linear { from: (f64, f64), to: (f64, f64) },
+    /// `turns` times round, counterclockwise from `phase` turns past the positive real direction
+    Circle {
+        center: (f64, f64),
+        radius: f64,
+        #[serde(default = "one")]
+        turns: f64,
+        #[serde(default)]
+        phase: f64,
+    },
+    /// Smoothly through every point, at `times` from 0 to 1 or evenly spaced if there aren't any
+    Keyframes {
+        points: Vec<(f64, f64)>,
+        #[serde(default)]
+        times: Vec<f64>,
+    },
+}
+
+fn one() -> f64 {
+    1f64
+}
+
+impl Path {
+    /// Where it is `t` of the way through, from 0 to 1.
+    pub fn at(&self, t: f64) -> Complex<f64> {
+        match self {
+            Path::Fixed { at } => complex(*at),
+            Path::Linear { from, to } => complex(*from) + (complex(*to) - complex(*from))*t,
+            Path::Circle { center, radius, turns, phase } => {
+                let angle = 2f64*std::f64::consts::PI*(phase + turns*t);
+                complex(*center) + Complex::from_polar(*radius, angle)
+            },
+            Path::Keyframes { points, times } => keyframes(points, times, t),
+        }
+    }
+
+    /// Whether it goes anywhere, which keyframes don't without any points or with times that don't fit them.
+    pub fn check(&self) -> Result<(), String> {
+        if let Path::Keyframes { points, times } = self {
+            if points.is_empty() {
+                return Err(String::from("keyframes need at least one point"));
+            }
+            if !times.is_empty() && times.len() != points.len() {
+                return Err(format!("keyframes have {} points but {} times", points.len(), times.len()));
+            }
+            if !times.windows(2).all(|pair| pair[0] <= pair[1]) {
+                return Err(String::from("keyframe times have to go in order"));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A Catmull-Rom spline through `points`, which passes through every one of them at its time.
+fn keyframes(points: &[(f64, f64)], times: &[f64], t: f64) -> Complex<f64> {
+    let last = points.len() - 1;
+    if last == 0 {
+        return complex(points[0]);
+    }
+
+    let time = |i: usize| if times.is_empty() { (i as f64)/(last as f64) } else { times[i] };
+
+    // the segment `t` is in, and how far along it
+    let segment = (0..last).find(|i| t <= time(i + 1)).unwrap_or(last - 1);
+    let length = time(segment + 1) - time(segment);
+    let u = if length > 0f64 { ((t - time(segment))/length).clamp(0f64, 1f64) } else { 0f64 };
+
+    let point = |i: i64| complex(points[i.clamp(0, last as i64) as usize]);
+    let (p0, p1, p2, p3) = (point(segment as i64 - 1), point(segment as i64), point(segment as i64 + 1), point(segment as i64 + 2));
+
+    (p1*2f64 + (p2 - p0)*u + (p0*2f64 - p1*5f64 + p2*4f64 - p3)*u*u + (p1*3f64 - p0 - p2*3f64 + p3)*u*u*u)*0.5f64
+}
+
+/// An animation of the polynomial itself, loaded from a TOML file. Either the roots move, as
+/// `[[root]]` sections, or the coefficients do, as `[[coefficient]]` sections lowest power first,
+/// or it goes smoothly through the random polynomials of `seeds`, made like `[random]` says.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Scene {
+    pub frames: usize,
+    #[serde(default = "Scene::fps")]
+    pub fps: u32,
+    #[serde(default)]
+    pub gif: bool,
+    /// Slowing down and speeding up the whole thing
+    #[serde(default = "Scene::easing")]
+    pub easing: Easing,
+    #[serde(default = "Scene::size")]
+    pub size: (usize, usize),
+    #[serde(default = "Scene::view")]
+    pub view: Rectangle<f64>,
+
+    /// What the roots get multiplied out with
+    #[serde(default = "Scene::leading")]
+    pub leading: (f64, f64),
+    #[serde(default, rename = "root")]
+    pub roots: Vec<Path>,
+    #[serde(default, rename = "coefficient")]
+    pub coefficients: Vec<Path>,
+    #[serde(default)]
+    pub seeds: Vec<u64>,
+    #[serde(default)]
+    pub random: Random,
+}
+
+impl Scene {
+    fn fps() -> u32 {
+        30
+    }
+
+    fn easing() -> Easing {
+        Easing::Linear
+    }
+
+    fn size() -> (usize, usize) {
+        (800, 800)
+    }
+
+    fn view() -> Rectangle<f64> {
+        Rectangle { left: -5f64, top: -5f64, right: 5f64, bottom: 5f64 }
+    }
+
+    fn leading() -> (f64, f64) {
+        (1f64, 0f64)
+    }
+
+    pub fn parse(contents: &str) -> Result<Scene, String> {
+        let mut scene: Scene = toml::from_str(contents).map_err(|e| e.to_string())?;
+
+        let sources = [!scene.roots.is_empty(), !scene.coefficients.is_empty(), !scene.seeds.is_empty()];
+        if sources.iter().filter(|given| **given).count() != 1 {
+            return Err(String::from("needs one of [[root]]s, [[coefficient]]s or seeds"));
+        }
+
+        for path in scene.roots.iter().chain(&scene.coefficients) {
+            path.check()?;
+        }
+        if !scene.seeds.is_empty() {
+            scene.random.distribution.check()?;
+
+            // each coefficient goes through its value in every one of the seeds' polynomials in turn
+            let mut polynomials: Vec<Vec<Complex<f64>>> = Vec::new();
+            for seed in &scene.seeds {
+                let (coefficients, fits) = scene.random.coefficients(*seed, &scene.view);
+                if !fits {
+                    return Err(format!("seed {} doesn't have every root in the view", seed));
+                }
+                polynomials.push(coefficients);
+            }
+            scene.coefficients = (0..polynomials[0].len())
+                .map(|power| Path::Keyframes {
+                    points: polynomials.iter().map(|coefficients| (coefficients[power].re, coefficients[power].im)).collect(),
+                    times: Vec::new(),
+                })
+                .collect();
+        }
+        if scene.frames == 0 {
+            return Err(String::from("needs at least one frame"));
+        }
+
+        Ok(scene)
+    }
+
+    pub fn load(path: &str) -> Result<Scene, String> {
+        let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Scene::parse(&contents)
+    }
+
+    pub fn video(&self) -> Video {
+        Video { frames: self.frames, easing: self.easing, gif: self.gif, fps: self.fps }
+    }
+
+    /// The coefficients for frame `frame`, counting from 0.
+    pub fn polynomial(&self, frame: usize) -> Vec<Complex<f64>> {
+        let t = if self.frames > 1 { (frame as f64)/((self.frames - 1) as f64) } else { 0f64 };
+        let t = self.easing.apply(t);
+
+        if self.roots.is_empty() {
+            self.coefficients.iter().map(|path| path.at(t)).collect()
+        } else {
+            let roots: Vec<Complex<f64>> = self.roots.iter().map(|path| path.at(t)).collect();
+            polynomial::from_roots(complex(self.leading), &roots)
+        }
+    }
+
+    /// Renders every frame into `directory`, like `animation::render`.
+    pub fn render(&self, directory: &str, options: SaveOptions, mut progress: impl FnMut(f64)) -> Result<(), String> {
+        let mut frames = Frames::new(directory, &self.video())?;
+
+        // reused like in `animation::render`, so each root is matched up with where it just was
+        let mut fractal = Fractal::with_coefficients(self.size, self.view, Vec::new());
+        for i in 0..self.frames {
+            fractal.set_coefficients(self.polynomial(i));
+            fractal.generate();
+            frames.write(&fractal, options)?;
+
+            progress(((i + 1) as f64)/(self.frames as f64));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::random::Distribution;
+
+    const ORBITING: &str = r#"
+        frames = 5
+        easing = "ease-in-out"
+
+        [[root]]
+        path = "fixed"
+        at = [0, 0]
+
+        [[root]]
+        path = "circle"
+        center = [0, 0]
+        radius = 2
+        turns = 0.25
+
+        [[root]]
+        path = "keyframes"
+        points = [[-1, 1], [-2, 0], [-1, -1]]
+    "#;
+
+    #[test]
+    fn paths_go_where_they_say() {
+        let circle = Path::Circle { center: (1f64, 0f64), radius: 2f64, turns: 1f64, phase: 0f64 };
+        assert!((circle.at(0.25f64) - Complex::new(1f64, 2f64)).norm() < 1e-12);
+
+        let keyframes = Path::Keyframes { points: vec![(0f64, 0f64), (1f64, 1f64), (3f64, 0f64)], times: vec![0f64, 0.75f64, 1f64] };
+        assert_eq!(keyframes.at(0f64), Complex::new(0f64, 0f64));
+        assert!((keyframes.at(0.75f64) - Complex::new(1f64, 1f64)).norm() < 1e-12);
+        assert_eq!(keyframes.at(1f64), Complex::new(3f64, 0f64));
+    }
+
+    #[test]
+    fn scenes_need_something_to_animate() {
+        assert!(Scene::parse("frames = 10").is_err());
+
+        let scene = Scene::parse(ORBITING).expect("scene");
+        assert_eq!(scene.roots.len(), 3);
+        assert_eq!(scene.size, (800, 800));
+        assert_eq!(scene.polynomial(0).len(), 4);
+
+        let example = Scene::parse(include_str!("../scenes/orbiting.toml")).expect("example scene");
+        assert_eq!(example.polynomial(0).len(), 5);
+    }
+
+    #[test]
+    fn keyframes_have_to_make_sense() {
+        let scene = |keyframes: &str| Scene::parse(&format!("frames = 2\n[[root]]\npath = \"keyframes\"\n{}", keyframes));
+
+        assert!(scene("points = []").is_err());
+        assert!(scene("points = [[0, 0], [1, 1]]\ntimes = [0]").is_err());
+        assert!(scene("points = [[0, 0], [1, 1], [2, 0]]\ntimes = [0, 0.8, 0.5]").is_err());
+        assert!(scene("points = [[0, 0], [1, 1]]\ntimes = [0, 1]").is_ok());
+        assert!(scene("points = [[0, 0]]").is_ok());
+    }
+
+    #[test]
+    fn seeds_are_keyframes() {
+        let scene = Scene::parse(r#"
+            frames = 3
+            seeds = [1, 2, 3]
+
+            [random]
+            degree = 3
+            monic = true
+            distribution = { kind = "disc", radius = 2 }
+        "#).expect("scene");
+
+        let random = Random { degree: 3, distribution: Distribution::Disc { radius: 2f64 }, monic: true, roots_in_view: false };
+        for (frame, seed) in [1, 2, 3].iter().enumerate() {
+            let (expected, _) = random.coefficients(*seed, &scene.view);
+            for (a, b) in scene.polynomial(frame).iter().zip(&expected) {
+                assert!((a - b).norm() < 1e-12, "frame {}: {} isn't {}", frame, a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn roots_keep_their_colors_as_they_move() {
+        let scene = Scene::parse(ORBITING).expect("scene");
+        let mut fractal = Fractal::with_coefficients((4, 4), scene.view, Vec::new());
+
+        let path_of = |root: &Complex<f64>, t: f64| scene.roots.iter().position(|path| (path.at(t) - root).norm() < 1e-6);
+
+        // whichever order they come out in to begin with, each one stays on its own path
+        let mut paths: Option<Vec<Option<usize>>> = None;
+        for frame in 0..scene.frames {
+            fractal.set_coefficients(scene.polynomial(frame));
+
+            let t = Easing::EaseInOut.apply((frame as f64)/4f64);
+            let now: Vec<Option<usize>> = fractal.roots().iter().map(|root| path_of(root, t)).collect();
+            assert!(now.iter().all(|path| path.is_some()), "frame {}: {:?}", frame, fractal.roots());
+
+            assert_eq!(paths.get_or_insert_with(|| now.clone()), &now, "frame {}", frame);
+        }
+    }
+
+    #[test]
+    fn leading_coefficients_can_go_through_zero() {
+        let scene = Scene::parse(r#"
+            frames = 3
+            size = [4, 4]
+
+            [[coefficient]]
+            path = "fixed"
+            at = [-1, 0]
+
+            [[coefficient]]
+            path = "fixed"
+            at = [0, 0]
+
+            [[coefficient]]
+            path = "linear"
+            from = [1, 0]
+            to = [-1, 0]
+        "#).expect("scene");
+
+        // the middle frame is just the constant -1, with no roots at all
+        let mut fractal = Fractal::with_coefficients(scene.size, scene.view, Vec::new());
+        for frame in 0..scene.frames {
+            fractal.set_coefficients(scene.polynomial(frame));
+            fractal.generate();
+            assert_eq!(fractal.roots().len(), if frame == 1 { 0 } else { 2 }, "frame {}", frame);
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use std::ops::{Add, Sub, Mul, Div};
+
+use num::complex::Complex;
+use num::Float;
+
+use crate::fractals::{Fractal, Newton};
+
+/// Floats that know how many of themselves fit in a vector register (256 bits).
+pub trait SimdFloat: Float {
+    fn get_roots(newton: &Newton<Self>, starts: &[Complex<Self>]) -> Vec<Option<(Complex<Self>, u32)>>;
+}
+
+impl SimdFloat for f32 {
+    fn get_roots(newton: &Newton<f32>, starts: &[Complex<f32>]) -> Vec<Option<(Complex<f32>, u32)>> {
+        newton.get_roots::<8>(starts)
+    }
+}
+
+impl SimdFloat for f64 {
+    fn get_roots(newton: &Newton<f64>, starts: &[Complex<f64>]) -> Vec<Option<(Complex<f64>, u32)>> {
+        newton.get_roots::<4>(starts)
+    }
+}
+
+/// `N` floats operated on together. Plain arrays rather than intrinsics, so it
+/// works on any target and the compiler lowers the lane loops to vector instructions.
+#[derive(Clone, Copy)]
+struct Lanes<T, const N: usize>([T; N]);
+
+macro_rules! lanes_binop {
+    ($trait:ident, $method:ident, $op:tt) => {
+        impl<T: Float, const N: usize> $trait for Lanes<T, N> {
+            type Output = Self;
+
+            #[inline(always)]
+            fn $method(self, other: Self) -> Self {
+                let mut out = self.0;
+                out.iter_mut().zip(other.0.iter()).for_each(|(a, b)| *a = *a $op *b);
+                Lanes(out)
+            }
+        }
+    };
+}
+
+lanes_binop!(Add, add, +);
+lanes_binop!(Sub, sub, -);
+lanes_binop!(Mul, mul, *);
+lanes_binop!(Div, div, /);
+
+impl<T: Float, const N: usize> Lanes<T, N> {
+    #[inline(always)]
+    fn splat(value: T) -> Self {
+        Lanes([value; N])
+    }
+
+    #[inline(always)]
+    fn lt(self, other: Self) -> [bool; N] {
+        let mut mask = [false; N];
+        mask.iter_mut().enumerate().for_each(|(i, m)| *m = self.0[i] < other.0[i]);
+        mask
+    }
+}
+
+/// `N` complex numbers, with the arithmetic spelled out exactly as `num::Complex` does it
+/// so every lane rounds the same way the scalar path would.
+#[derive(Clone, Copy)]
+struct ComplexLanes<T, const N: usize> {
+    re: Lanes<T, N>,
+    im: Lanes<T, N>,
+}
+
+impl<T: Float, const N: usize> ComplexLanes<T, N> {
+    #[inline(always)]
+    fn splat(c: Complex<T>) -> Self {
+        ComplexLanes { re: Lanes::splat(c.re), im: Lanes::splat(c.im) }
+    }
+
+    #[inline(always)]
+    fn add(self, other: Self) -> Self {
+        ComplexLanes { re: self.re + other.re, im: self.im + other.im }
+    }
+
+    #[inline(always)]
+    fn sub(self, other: Self) -> Self {
+        ComplexLanes { re: self.re - other.re, im: self.im - other.im }
+    }
+
+    #[inline(always)]
+    fn mul(self, other: Self) -> Self {
+        ComplexLanes {
+            re: self.re*other.re - self.im*other.im,
+            im: self.re*other.im + self.im*other.re,
+        }
+    }
+
+    #[inline(always)]
+    fn div(self, other: Self) -> Self {
+        let norm_sqr = other.norm_sqr();
+        ComplexLanes {
+            re: (self.re*other.re + self.im*other.im) / norm_sqr,
+            im: (self.im*other.re - self.re*other.im) / norm_sqr,
+        }
+    }
+
+    #[inline(always)]
+    fn norm_sqr(self) -> Lanes<T, N> {
+        self.re*self.re + self.im*self.im
+    }
+
+    #[inline(always)]
+    fn evaluate(polynomial: &[Complex<T>], z: Self) -> Self {
+        polynomial.iter().rev().fold(Self::splat(Complex::new(T::zero(), T::zero())), |result, c| result.mul(z).add(Self::splat(*c)))
+    }
+}
+
+impl<T: Float> Newton<T> {
+    /// Same as calling `get_root` on every start, but iterating `N` starts at a time.
+    /// Whatever doesn't fill a whole group of lanes falls back to the scalar path.
+    pub fn get_roots<const N: usize>(&self, starts: &[Complex<T>]) -> Vec<Option<(Complex<T>, u32)>> {
+        let mut roots = Vec::with_capacity(starts.len());
+
+        let chunks = starts.chunks_exact(N);
+        let remainder = chunks.remainder();
+
+        for chunk in chunks {
+            let mut z = ComplexLanes::<T, N> {
+                re: Lanes(std::array::from_fn(|i| chunk[i].re)),
+                im: Lanes(std::array::from_fn(|i| chunk[i].im)),
+            };
+
+            roots.extend_from_slice(&self.get_root_lanes(&mut z));
+        }
+
+        roots.extend(remainder.iter().map(|s| self.get_root(*s)));
+
+        roots
+    }
+
+    fn get_root_lanes<const N: usize>(&self, z: &mut ComplexLanes<T, N>) -> [Option<(Complex<T>, u32)>; N] {
+        let tolerance = Lanes::splat(self.tolerance*self.tolerance);
+
+        let mut roots = [None; N];
+        let mut converged = [false; N];
+
+        for i in 0..Fractal::MAX_ITERS {
+            let z_prev = *z;
+            *z = z.sub(ComplexLanes::evaluate(&self.coefficients, *z).div(ComplexLanes::evaluate(&self.derivative, *z)));
+
+            // converged lanes keep iterating along with the rest, their results are just no longer recorded
+            let mask = z.sub(z_prev).norm_sqr().lt(tolerance);
+            for lane in 0..N {
+                if mask[lane] && !converged[lane] {
+                    converged[lane] = true;
+                    roots[lane] = Some((Complex::new(z.re.0[lane], z.im.0[lane]), i));
+                }
+            }
+
+            if converged.iter().all(|c| *c) {
+                break;
+            }
+        }
+
+        roots
+    }
+}
@@ -0,0 +1,23 @@
+//! Polynomials and files shared by the tests.
+
+use num::complex::Complex;
+
+/// z^n - 1, lowest power first, whose roots are the nth roots of unity.
+pub fn unity(n: usize) -> Vec<Complex<f64>> {
+    let mut coefficients = vec![Complex::new(0f64, 0f64); n + 1];
+    coefficients[0] = Complex::new(-1f64, 0f64);
+    coefficients[n] = Complex::new(1f64, 0f64);
+    coefficients
+}
+
+/// z^3 - 2z + 2, which sends 0 to 1 and 1 straight back to 0, so everything close to
+/// either never converges.
+pub fn cycle() -> Vec<Complex<f64>> {
+    vec![Complex::new(2f64, 0f64), Complex::new(-2f64, 0f64), Complex::new(0f64, 0f64), Complex::new(1f64, 0f64)]
+}
+
+/// Somewhere in the temp directory called `name`, that other runs at the same time won't touch.
+pub fn temp_path(name: &str) -> String {
+    let path = std::env::temp_dir().join(format!("newtonian_rustsody_{}_{}", std::process::id(), name));
+    path.to_str().expect("utf-8 temp dir").to_string()
+}
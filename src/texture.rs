@@ -0,0 +1,62 @@
+use ggez::{Context, GameResult};
+use ggez::graphics::{self, BlendMode, Drawable, DrawParam, Image, Rect};
+
+use newtonian_rustsody::fractals::Fractal;
+
+/// A fractal's pixels on the GPU, only uploaded again when they've changed.
+pub struct FractalImage {
+    image: Option<Image>,
+    generation: Option<u64>,
+    blend_mode: Option<BlendMode>,
+}
+
+impl FractalImage {
+    pub fn new() -> FractalImage {
+        FractalImage {
+            image: None,
+            generation: None,
+            blend_mode: None,
+        }
+    }
+
+    /// Brings the image up to date with `fractal`, which does nothing if it already is.
+    pub fn update(&mut self, ctx: &mut Context, fractal: &Fractal) -> GameResult {
+        if self.generation == Some(fractal.generation()) {
+            return Ok(());
+        }
+
+        let (width, height) = fractal.size();
+        let mut image = Image::from_rgba8(ctx, width as u16, height as u16, fractal.pixels())?;
+        image.set_blend_mode(self.blend_mode);
+
+        self.image = Some(image);
+        self.generation = Some(fractal.generation());
+
+        Ok(())
+    }
+}
+
+impl Drawable for FractalImage {
+    fn draw(&self, ctx: &mut Context, param: DrawParam) -> GameResult {
+        match &self.image {
+            Some(image) => graphics::draw(ctx, image, param),
+            None => Ok(()), // nothing generated yet
+        }
+    }
+
+    fn dimensions(&self, _ctx: &mut Context) -> Option<Rect> {
+        self.image.as_ref().map(|image| image.dimensions())
+    }
+
+    /// Kept for whichever image comes next, as well as the one there now.
+    fn set_blend_mode(&mut self, mode: Option<BlendMode>) {
+        self.blend_mode = mode;
+        if let Some(image) = &mut self.image {
+            image.set_blend_mode(mode);
+        }
+    }
+
+    fn blend_mode(&self) -> Option<BlendMode> {
+        self.blend_mode
+    }
+}